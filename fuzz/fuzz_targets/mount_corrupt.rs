@@ -0,0 +1,259 @@
+//! Mount corrupted filesystem images.
+//!
+//! A valid image is built once, then mutated with bit flips, overwrites, truncation and block
+//! swaps before being mounted. The whole tree is walked and every file is read back. Any error
+//! returned by littlefs is acceptable; panics, hangs and accesses outside of the image are not.
+
+#![no_main]
+
+use std::cell::{Cell, RefCell};
+use std::rc::Rc;
+use std::sync::OnceLock;
+
+use arbitrary::Arbitrary;
+use libfuzzer_sys::fuzz_target;
+use littlefs::{Dir, EntryType, File, FileOpenFlags, FsError, Info, LittleFs, Storage};
+
+/// Default flash erase value.
+const ERASE_VALUE: u8 = 0xFF;
+
+/// Geometry of the filesystem as configured by `LittleFs`.
+const BLOCK_SIZE: usize = 4096;
+const BLOCK_COUNT: usize = 32;
+const STORAGE_SIZE: usize = BLOCK_SIZE * BLOCK_COUNT;
+
+/// Number of storage operations after which littlefs is considered to be stuck in a loop.
+const OPERATION_BUDGET: u32 = 200_000;
+
+/// Deepest directory nesting followed while walking the tree.
+const MAX_DEPTH: usize = 8;
+
+/// Most entries read from a single directory.
+const MAX_ENTRIES: usize = 64;
+
+/// Most bytes read from a single file.
+const MAX_FILE_READ: usize = 64 * 1024;
+
+/// Storage backed by an image held in RAM. Accesses past the end of the image fail with an I/O
+/// error and every operation is counted against a budget to detect livelocks.
+#[derive(Clone)]
+struct ImageStorage {
+    image: Rc<RefCell<Vec<u8>>>,
+    budget: Rc<Cell<u32>>,
+}
+
+impl ImageStorage {
+    fn new(image: Vec<u8>) -> Self {
+        ImageStorage {
+            image: Rc::new(RefCell::new(image)),
+            budget: Rc::new(Cell::new(OPERATION_BUDGET)),
+        }
+    }
+
+    fn spend(&self) {
+        let budget = self.budget.get();
+        assert!(budget > 0, "storage operation budget exhausted");
+        self.budget.set(budget - 1);
+    }
+}
+
+impl Storage for ImageStorage {
+    fn read(&self, off: usize, buf: &mut [u8]) -> Result<usize, FsError> {
+        self.spend();
+        let image = self.image.borrow();
+        let data = image.get(off..off + buf.len()).ok_or(FsError::Io)?;
+        buf.copy_from_slice(data);
+        Ok(buf.len())
+    }
+
+    fn write(&mut self, off: usize, data: &[u8]) -> Result<usize, FsError> {
+        self.spend();
+        let mut image = self.image.borrow_mut();
+        let dest = image.get_mut(off..off + data.len()).ok_or(FsError::Io)?;
+        dest.copy_from_slice(data);
+        Ok(data.len())
+    }
+
+    fn erase(&mut self, off: usize, len: usize) -> Result<usize, FsError> {
+        self.spend();
+        let mut image = self.image.borrow_mut();
+        let dest = image.get_mut(off..off + len).ok_or(FsError::Io)?;
+        for byte in dest {
+            *byte = ERASE_VALUE;
+        }
+        Ok(len)
+    }
+}
+
+#[derive(Arbitrary, Debug)]
+enum Mutation {
+    /// Flip a single bit.
+    BitFlip { offset: u32, bit: u8 },
+    /// Overwrite bytes starting at an offset.
+    Overwrite { offset: u32, data: Vec<u8> },
+    /// Cut the image short.
+    Truncate { len: u32 },
+    /// Exchange the contents of two blocks.
+    SwapBlocks { a: u8, b: u8 },
+    /// Reset a block to the erased state.
+    EraseBlock { block: u8 },
+}
+
+impl Mutation {
+    fn apply(&self, image: &mut Vec<u8>) {
+        if image.is_empty() {
+            return;
+        }
+        let len = image.len();
+        match *self {
+            Mutation::BitFlip { offset, bit } => {
+                image[offset as usize % len] ^= 1 << (bit % 8);
+            }
+            Mutation::Overwrite { offset, ref data } => {
+                let start = offset as usize % len;
+                let end = (start + data.len()).min(len);
+                image[start..end].copy_from_slice(&data[..end - start]);
+            }
+            Mutation::Truncate { len: new_len } => {
+                image.truncate(new_len as usize % len);
+            }
+            Mutation::SwapBlocks { a, b } => {
+                let blocks = len / BLOCK_SIZE;
+                if blocks == 0 {
+                    return;
+                }
+                let (a, b) = (a as usize % blocks, b as usize % blocks);
+                for i in 0..BLOCK_SIZE {
+                    image.swap(a * BLOCK_SIZE + i, b * BLOCK_SIZE + i);
+                }
+            }
+            Mutation::EraseBlock { block } => {
+                let start = (block as usize % BLOCK_COUNT) * BLOCK_SIZE;
+                let end = (start + BLOCK_SIZE).min(len);
+                if start < end {
+                    for byte in &mut image[start..end] {
+                        *byte = ERASE_VALUE;
+                    }
+                }
+            }
+        }
+    }
+}
+
+fn write_file(lfs: &mut LittleFs<ImageStorage>, path: &str, data: &[u8]) {
+    let mut file = File::default();
+    lfs.file_open(
+        &mut file,
+        path,
+        FileOpenFlags::WRONLY | FileOpenFlags::CREAT | FileOpenFlags::TRUNC,
+    )
+    .unwrap();
+    lfs.file_write(&mut file, data).unwrap();
+    lfs.file_close(&mut file).unwrap();
+}
+
+/// Build a valid image holding nested directories, small files and a file spanning several
+/// blocks.
+fn build_image() -> Vec<u8> {
+    let storage = ImageStorage::new(vec![ERASE_VALUE; STORAGE_SIZE]);
+    let mut lfs = LittleFs::new(storage.clone());
+    lfs.format().unwrap();
+    let mut lfs = lfs.mount().unwrap();
+    lfs.mkdir("/etc").unwrap();
+    lfs.mkdir("/etc/net").unwrap();
+    lfs.mkdir("/log").unwrap();
+    write_file(&mut lfs, "/etc/config", b"baud=115200\nmode=auto\n");
+    write_file(&mut lfs, "/etc/net/address", b"192.168.0.1");
+    let log: Vec<u8> = (0..3 * BLOCK_SIZE).map(|i| i as u8).collect();
+    write_file(&mut lfs, "/log/0", &log);
+    write_file(&mut lfs, "/log/1", b"boot");
+    lfs.rename("/log/1", "/log/2").unwrap();
+    write_file(&mut lfs, "/tmp", b"scratch");
+    lfs.remove("/tmp").unwrap();
+    lfs.unmount().unwrap();
+
+    let image = storage.image.borrow().clone();
+    image
+}
+
+fn base_image() -> &'static [u8] {
+    static IMAGE: OnceLock<Vec<u8>> = OnceLock::new();
+    IMAGE.get_or_init(build_image)
+}
+
+fn name_of(info: &Info) -> String {
+    info.name.to_string()
+}
+
+fn read_file(lfs: &mut LittleFs<ImageStorage>, path: &str) {
+    let mut file = File::default();
+    if lfs.file_open(&mut file, path, FileOpenFlags::RDONLY).is_err() {
+        return;
+    }
+    let _ = lfs.file_size(&mut file);
+    let mut buf = [0u8; 256];
+    let mut total = 0;
+    while total < MAX_FILE_READ {
+        match lfs.file_read(&mut file, &mut buf) {
+            Ok(0) | Err(_) => break,
+            Ok(sz) => total += sz,
+        }
+    }
+    let _ = lfs.file_close(&mut file);
+}
+
+fn walk(lfs: &mut LittleFs<ImageStorage>, path: &str, depth: usize) {
+    if depth > MAX_DEPTH {
+        return;
+    }
+
+    let mut dir = Dir::default();
+    if lfs.dir_open(&mut dir, path).is_err() {
+        return;
+    }
+    let mut entries = Vec::new();
+    while entries.len() < MAX_ENTRIES {
+        match lfs.dir_read(&mut dir) {
+            Ok(Some(info)) => entries.push(info),
+            Ok(None) | Err(_) => break,
+        }
+    }
+    let _ = lfs.dir_close(&mut dir);
+
+    for info in entries {
+        let name = name_of(&info);
+        if name == "." || name == ".." {
+            continue;
+        }
+        let child = if path == "/" {
+            format!("/{}", name)
+        } else {
+            format!("{}/{}", path, name)
+        };
+        let mut stat = Info::default();
+        let _ = lfs.stat(&child, &mut stat);
+        match info.entry_type {
+            EntryType::Directory => walk(lfs, &child, depth + 1),
+            EntryType::RegularFile => read_file(lfs, &child),
+        }
+    }
+}
+
+fuzz_target!(|mutations: Vec<Mutation>| {
+    let mut image = base_image().to_vec();
+    for mutation in mutations.iter() {
+        mutation.apply(&mut image);
+    }
+
+    match LittleFs::new(ImageStorage::new(image)).mount() {
+        Ok(mut lfs) => {
+            walk(&mut lfs, "/", 0);
+            let _ = lfs.unmount();
+        }
+        Err(err) => assert!(
+            matches!(err.error, FsError::Corrupt | FsError::Io | FsError::Inval),
+            "unexpected mount error {:?}",
+            err.error
+        ),
+    }
+});
@@ -9,16 +9,67 @@
 // Author: Brandon Edens <brandonedens@gmail.com>
 // Date: 2018-11-10
 
+#[cfg(feature = "bindgen")]
 use std::env;
+#[cfg(feature = "bindgen")]
 use std::path::PathBuf;
 
 fn main() {
-    cc::Build::new()
+    for path in &[
+        "littlefs/lfs.c",
+        "littlefs/lfs.h",
+        "littlefs/lfs_util.c",
+        "littlefs/lfs_util.h",
+    ] {
+        println!("cargo:rerun-if-changed={}", path);
+    }
+
+    let mut build = cc::Build::new();
+    build
         .flag("-std=c11")
         .file("littlefs/lfs.c")
-        .file("littlefs/lfs_util.c")
-        .compile("lfs-sys");
+        .file("littlefs/lfs_util.c");
+
+    // Replace lfs_util.h with a header forwarding log messages and assertions to hooks that are
+    // installed from Rust.
+    if cfg!(feature = "rust-util") {
+        build
+            .include("csrc")
+            .file("csrc/lfs_rust_util.c")
+            .define("LFS_CONFIG", "lfs_rust_util.h");
+        println!("cargo:rerun-if-changed=csrc/lfs_rust_util.h");
+        println!("cargo:rerun-if-changed=csrc/lfs_rust_util.c");
+    }
+
+    // Map cargo features onto the configuration macros of lfs_util.h.
+    if !cfg!(feature = "debug") {
+        build.define("LFS_NO_DEBUG", None);
+    }
+    if !cfg!(feature = "warn") {
+        build.define("LFS_NO_WARN", None);
+    }
+    if !cfg!(feature = "error") {
+        build.define("LFS_NO_ERROR", None);
+    }
+    if !cfg!(feature = "assert") {
+        build.define("LFS_NO_ASSERT", None);
+    }
+    if !cfg!(feature = "malloc") {
+        build.define("LFS_NO_MALLOC", None);
+    }
+    if cfg!(feature = "alloc") {
+        build.define("LFS_RUST_ALLOC", None);
+    }
+    build.compile("lfs-sys");
+
+    #[cfg(feature = "bindgen")]
+    generate_bindings();
+}
 
+/// Generate bindings for the vendored headers into OUT_DIR. Without the `bindgen` feature the
+/// pre-generated src/bindings.rs is used instead, so libclang is not needed to build the crate.
+#[cfg(feature = "bindgen")]
+fn generate_bindings() {
     let bindings = bindgen::Builder::default()
         .header("littlefs/lfs.h")
         .use_core()
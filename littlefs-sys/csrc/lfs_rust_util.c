@@ -0,0 +1,16 @@
+/*
+ * Hooks used by lfs_rust_util.h to forward littlefs log messages, assertions
+ * and CRC computation to Rust.
+ */
+#include "lfs_rust_util.h"
+
+lfs_rust_log_t lfs_rust_log_hook = NULL;
+lfs_rust_assert_t lfs_rust_assert_hook = NULL;
+lfs_rust_crc_t lfs_rust_crc_hook = NULL;
+
+void lfs_rust_assert(const char *file, int line, const char *expr) {
+    if (lfs_rust_assert_hook) {
+        lfs_rust_assert_hook(file, line, expr);
+    }
+    __builtin_trap();
+}
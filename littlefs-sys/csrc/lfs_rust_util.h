@@ -0,0 +1,243 @@
+/*
+ * Replacement for lfs_util.h used when littlefs-sys is built with the
+ * `rust-util` feature. It is selected through LFS_CONFIG and forwards the
+ * littlefs log messages and assertions to hooks installed from Rust instead
+ * of relying on printf and assert from libc. The CRC of metadata may also be
+ * computed by a hook, for example to make use of a CRC peripheral.
+ *
+ * The utility functions below are those of the upstream lfs_util.h.
+ *
+ * Copyright (c) 2017, Arm Limited. All rights reserved.
+ * SPDX-License-Identifier: BSD-3-Clause
+ */
+#ifndef LFS_RUST_UTIL_H
+#define LFS_RUST_UTIL_H
+
+#include <stdint.h>
+#include <stdbool.h>
+#include <stddef.h>
+#include <string.h>
+#include <inttypes.h>
+
+#if !defined(LFS_NO_MALLOC) && !defined(LFS_RUST_ALLOC)
+#include <stdlib.h>
+#endif
+
+#ifdef __cplusplus
+extern "C"
+{
+#endif
+
+
+// Log levels handed to the log hook, numbered like the levels of the Rust
+// log crate
+#define LFS_RUST_LOG_ERROR 1
+#define LFS_RUST_LOG_WARN  2
+#define LFS_RUST_LOG_DEBUG 4
+#define LFS_RUST_LOG_TRACE 5
+
+// Hook receiving log messages. The format string uses printf syntax and all
+// of its arguments are passed as 32-bit integers
+typedef void (*lfs_rust_log_t)(int level, const char *file, int line,
+        const char *fmt, const uint32_t *args, size_t nargs);
+
+// Hook receiving failed assertions, it must not return
+typedef void (*lfs_rust_assert_t)(const char *file, int line,
+        const char *expr);
+
+// Hook computing the CRC of littlefs metadata, continuing from the given
+// value. Replaces the software implementation when installed
+typedef uint32_t (*lfs_rust_crc_t)(uint32_t crc, const void *buffer,
+        size_t size);
+
+extern lfs_rust_log_t lfs_rust_log_hook;
+extern lfs_rust_assert_t lfs_rust_assert_hook;
+extern lfs_rust_crc_t lfs_rust_crc_hook;
+
+// Report a failed assertion to the hook, trapping if none is installed
+void lfs_rust_assert(const char *file, int line, const char *expr);
+
+#ifdef LFS_RUST_ALLOC
+// Allocate and release memory through the Rust global allocator
+void *lfs_rust_malloc(size_t size);
+void lfs_rust_free(void *p);
+#endif
+
+#define LFS_RUST_LOG(level, fmt, ...) do { \
+    if (lfs_rust_log_hook) { \
+        const uint32_t lfs_rust_args[] = {__VA_ARGS__}; \
+        lfs_rust_log_hook(level, __FILE__, __LINE__, fmt, lfs_rust_args, \
+                sizeof(lfs_rust_args) / sizeof(lfs_rust_args[0])); \
+    } \
+} while (0)
+
+// Logging functions
+#ifdef LFS_YES_TRACE
+#define LFS_TRACE(fmt, ...) LFS_RUST_LOG(LFS_RUST_LOG_TRACE, fmt, __VA_ARGS__)
+#else
+#define LFS_TRACE(fmt, ...)
+#endif
+
+#ifndef LFS_NO_DEBUG
+#define LFS_DEBUG(fmt, ...) LFS_RUST_LOG(LFS_RUST_LOG_DEBUG, fmt, __VA_ARGS__)
+#else
+#define LFS_DEBUG(fmt, ...)
+#endif
+
+#ifndef LFS_NO_WARN
+#define LFS_WARN(fmt, ...) LFS_RUST_LOG(LFS_RUST_LOG_WARN, fmt, __VA_ARGS__)
+#else
+#define LFS_WARN(fmt, ...)
+#endif
+
+#ifndef LFS_NO_ERROR
+#define LFS_ERROR(fmt, ...) LFS_RUST_LOG(LFS_RUST_LOG_ERROR, fmt, __VA_ARGS__)
+#else
+#define LFS_ERROR(fmt, ...)
+#endif
+
+// Runtime assertions
+#ifndef LFS_NO_ASSERT
+#define LFS_ASSERT(test) do { \
+    if (!(test)) { \
+        lfs_rust_assert(__FILE__, __LINE__, #test); \
+    } \
+} while (0)
+#else
+#define LFS_ASSERT(test)
+#endif
+
+
+// Builtin functions, these may be replaced by more efficient
+// toolchain-specific implementations. LFS_NO_INTRINSICS falls back to a more
+// expensive basic C implementation for debugging purposes
+
+// Min/max functions for unsigned 32-bit numbers
+static inline uint32_t lfs_max(uint32_t a, uint32_t b) {
+    return (a > b) ? a : b;
+}
+
+static inline uint32_t lfs_min(uint32_t a, uint32_t b) {
+    return (a < b) ? a : b;
+}
+
+// Find the next smallest power of 2 less than or equal to a
+static inline uint32_t lfs_npw2(uint32_t a) {
+#if !defined(LFS_NO_INTRINSICS) && (defined(__GNUC__) || defined(__CC_ARM))
+    return 32 - __builtin_clz(a-1);
+#else
+    uint32_t r = 0;
+    uint32_t s;
+    a -= 1;
+    s = (a > 0xffff) << 4; a >>= s; r |= s;
+    s = (a > 0xff  ) << 3; a >>= s; r |= s;
+    s = (a > 0xf   ) << 2; a >>= s; r |= s;
+    s = (a > 0x3   ) << 1; a >>= s; r |= s;
+    return (r | (a >> 1)) + 1;
+#endif
+}
+
+// Count the number of trailing binary zeros in a
+// lfs_ctz(0) may be undefined
+static inline uint32_t lfs_ctz(uint32_t a) {
+#if !defined(LFS_NO_INTRINSICS) && defined(__GNUC__)
+    return __builtin_ctz(a);
+#else
+    return lfs_npw2((a & -a) + 1) - 1;
+#endif
+}
+
+// Count the number of binary ones in a
+static inline uint32_t lfs_popc(uint32_t a) {
+#if !defined(LFS_NO_INTRINSICS) && (defined(__GNUC__) || defined(__CC_ARM))
+    return __builtin_popcount(a);
+#else
+    a = a - ((a >> 1) & 0x55555555);
+    a = (a & 0x33333333) + ((a >> 2) & 0x33333333);
+    return (((a + (a >> 4)) & 0xf0f0f0f) * 0x1010101) >> 24;
+#endif
+}
+
+// Find the sequence comparison of a and b, this is the distance
+// between a and b ignoring overflow
+static inline int lfs_scmp(uint32_t a, uint32_t b) {
+    return (int)(unsigned)(a - b);
+}
+
+// Convert from 32-bit little-endian to native order
+static inline uint32_t lfs_fromle32(uint32_t a) {
+#if !defined(LFS_NO_INTRINSICS) && ( \
+    (defined(  BYTE_ORDER  ) &&   BYTE_ORDER   ==   ORDER_LITTLE_ENDIAN  ) || \
+    (defined(__BYTE_ORDER  ) && __BYTE_ORDER   == __ORDER_LITTLE_ENDIAN  ) || \
+    (defined(__BYTE_ORDER__) && __BYTE_ORDER__ == __ORDER_LITTLE_ENDIAN__))
+    return a;
+#elif !defined(LFS_NO_INTRINSICS) && ( \
+    (defined(  BYTE_ORDER  ) &&   BYTE_ORDER   ==   ORDER_BIG_ENDIAN  ) || \
+    (defined(__BYTE_ORDER  ) && __BYTE_ORDER   == __ORDER_BIG_ENDIAN  ) || \
+    (defined(__BYTE_ORDER__) && __BYTE_ORDER__ == __ORDER_BIG_ENDIAN__))
+    return __builtin_bswap32(a);
+#else
+    return (((uint8_t*)&a)[0] <<  0) |
+           (((uint8_t*)&a)[1] <<  8) |
+           (((uint8_t*)&a)[2] << 16) |
+           (((uint8_t*)&a)[3] << 24);
+#endif
+}
+
+// Convert to 32-bit little-endian from native order
+static inline uint32_t lfs_tole32(uint32_t a) {
+    return lfs_fromle32(a);
+}
+
+// Calculate CRC-32 with polynomial = 0x04c11db7, upstream lfs_util.c only
+// provides this when no custom configuration is used
+static inline void lfs_crc(uint32_t *crc, const void *buffer, size_t size) {
+    if (lfs_rust_crc_hook) {
+        *crc = lfs_rust_crc_hook(*crc, buffer, size);
+        return;
+    }
+
+    static const uint32_t rtable[16] = {
+        0x00000000, 0x1db71064, 0x3b6e20c8, 0x26d930ac,
+        0x76dc4190, 0x6b6b51f4, 0x4db26158, 0x5005713c,
+        0xedb88320, 0xf00f9344, 0xd6d6a3e8, 0xcb61b38c,
+        0x9b64c2b0, 0x86d3d2d4, 0xa00ae278, 0xbdbdf21c,
+    };
+
+    const uint8_t *data = buffer;
+
+    for (size_t i = 0; i < size; i++) {
+        *crc = (*crc >> 4) ^ rtable[(*crc ^ (data[i] >> 0)) & 0xf];
+        *crc = (*crc >> 4) ^ rtable[(*crc ^ (data[i] >> 4)) & 0xf];
+    }
+}
+
+// Allocate memory, only used if buffers are not provided to littlefs
+static inline void *lfs_malloc(size_t size) {
+#if defined(LFS_RUST_ALLOC)
+    return lfs_rust_malloc(size);
+#elif !defined(LFS_NO_MALLOC)
+    return malloc(size);
+#else
+    (void)size;
+    return NULL;
+#endif
+}
+
+// Deallocate memory, only used if buffers are not provided to littlefs
+static inline void lfs_free(void *p) {
+#if defined(LFS_RUST_ALLOC)
+    lfs_rust_free(p);
+#elif !defined(LFS_NO_MALLOC)
+    free(p);
+#else
+    (void)p;
+#endif
+}
+
+
+#ifdef __cplusplus
+} /* extern "C" */
+#endif
+
+#endif
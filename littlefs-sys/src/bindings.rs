@@ -0,0 +1,1323 @@
+/* automatically generated by rust-bindgen 0.69.4 */
+
+pub const LFS_VERSION: u32 = 65543;
+pub const LFS_VERSION_MAJOR: u32 = 1;
+pub const LFS_VERSION_MINOR: u32 = 7;
+pub const LFS_DISK_VERSION: u32 = 65537;
+pub const LFS_DISK_VERSION_MAJOR: u32 = 1;
+pub const LFS_DISK_VERSION_MINOR: u32 = 1;
+pub const LFS_NAME_MAX: u32 = 255;
+pub const LFS_FILE_MAX: u32 = 2147483647;
+pub type lfs_size_t = u32;
+pub type lfs_off_t = u32;
+pub type lfs_ssize_t = i32;
+pub type lfs_soff_t = i32;
+pub type lfs_block_t = u32;
+pub const lfs_error_LFS_ERR_OK: lfs_error = 0;
+pub const lfs_error_LFS_ERR_IO: lfs_error = -5;
+pub const lfs_error_LFS_ERR_CORRUPT: lfs_error = -52;
+pub const lfs_error_LFS_ERR_NOENT: lfs_error = -2;
+pub const lfs_error_LFS_ERR_EXIST: lfs_error = -17;
+pub const lfs_error_LFS_ERR_NOTDIR: lfs_error = -20;
+pub const lfs_error_LFS_ERR_ISDIR: lfs_error = -21;
+pub const lfs_error_LFS_ERR_NOTEMPTY: lfs_error = -39;
+pub const lfs_error_LFS_ERR_BADF: lfs_error = -9;
+pub const lfs_error_LFS_ERR_FBIG: lfs_error = -27;
+pub const lfs_error_LFS_ERR_INVAL: lfs_error = -22;
+pub const lfs_error_LFS_ERR_NOSPC: lfs_error = -28;
+pub const lfs_error_LFS_ERR_NOMEM: lfs_error = -12;
+pub type lfs_error = cty::c_int;
+pub const lfs_type_LFS_TYPE_REG: lfs_type = 17;
+pub const lfs_type_LFS_TYPE_DIR: lfs_type = 34;
+pub const lfs_type_LFS_TYPE_SUPERBLOCK: lfs_type = 46;
+pub type lfs_type = cty::c_uint;
+pub const lfs_open_flags_LFS_O_RDONLY: lfs_open_flags = 1;
+pub const lfs_open_flags_LFS_O_WRONLY: lfs_open_flags = 2;
+pub const lfs_open_flags_LFS_O_RDWR: lfs_open_flags = 3;
+pub const lfs_open_flags_LFS_O_CREAT: lfs_open_flags = 256;
+pub const lfs_open_flags_LFS_O_EXCL: lfs_open_flags = 512;
+pub const lfs_open_flags_LFS_O_TRUNC: lfs_open_flags = 1024;
+pub const lfs_open_flags_LFS_O_APPEND: lfs_open_flags = 2048;
+pub const lfs_open_flags_LFS_F_DIRTY: lfs_open_flags = 65536;
+pub const lfs_open_flags_LFS_F_WRITING: lfs_open_flags = 131072;
+pub const lfs_open_flags_LFS_F_READING: lfs_open_flags = 262144;
+pub const lfs_open_flags_LFS_F_ERRED: lfs_open_flags = 524288;
+pub type lfs_open_flags = cty::c_uint;
+pub const lfs_whence_flags_LFS_SEEK_SET: lfs_whence_flags = 0;
+pub const lfs_whence_flags_LFS_SEEK_CUR: lfs_whence_flags = 1;
+pub const lfs_whence_flags_LFS_SEEK_END: lfs_whence_flags = 2;
+pub type lfs_whence_flags = cty::c_uint;
+#[repr(C)]
+#[derive(Debug, Copy, Clone)]
+pub struct lfs_config {
+    pub context: *mut cty::c_void,
+    pub read: ::core::option::Option<
+        unsafe extern "C" fn(
+            c: *const lfs_config,
+            block: lfs_block_t,
+            off: lfs_off_t,
+            buffer: *mut cty::c_void,
+            size: lfs_size_t,
+        ) -> cty::c_int,
+    >,
+    pub prog: ::core::option::Option<
+        unsafe extern "C" fn(
+            c: *const lfs_config,
+            block: lfs_block_t,
+            off: lfs_off_t,
+            buffer: *const cty::c_void,
+            size: lfs_size_t,
+        ) -> cty::c_int,
+    >,
+    pub erase: ::core::option::Option<
+        unsafe extern "C" fn(c: *const lfs_config, block: lfs_block_t) -> cty::c_int,
+    >,
+    pub sync: ::core::option::Option<unsafe extern "C" fn(c: *const lfs_config) -> cty::c_int>,
+    pub read_size: lfs_size_t,
+    pub prog_size: lfs_size_t,
+    pub block_size: lfs_size_t,
+    pub block_count: lfs_size_t,
+    pub lookahead: lfs_size_t,
+    pub read_buffer: *mut cty::c_void,
+    pub prog_buffer: *mut cty::c_void,
+    pub lookahead_buffer: *mut cty::c_void,
+    pub file_buffer: *mut cty::c_void,
+}
+#[test]
+fn bindgen_test_layout_lfs_config() {
+    const UNINIT: ::core::mem::MaybeUninit<lfs_config> = ::core::mem::MaybeUninit::uninit();
+    let ptr = UNINIT.as_ptr();
+    assert_eq!(
+        ::core::mem::size_of::<lfs_config>(),
+        96usize,
+        concat!("Size of: ", stringify!(lfs_config))
+    );
+    assert_eq!(
+        ::core::mem::align_of::<lfs_config>(),
+        8usize,
+        concat!("Alignment of ", stringify!(lfs_config))
+    );
+    assert_eq!(
+        unsafe { ::core::ptr::addr_of!((*ptr).context) as usize - ptr as usize },
+        0usize,
+        concat!(
+            "Offset of field: ",
+            stringify!(lfs_config),
+            "::",
+            stringify!(context)
+        )
+    );
+    assert_eq!(
+        unsafe { ::core::ptr::addr_of!((*ptr).read) as usize - ptr as usize },
+        8usize,
+        concat!(
+            "Offset of field: ",
+            stringify!(lfs_config),
+            "::",
+            stringify!(read)
+        )
+    );
+    assert_eq!(
+        unsafe { ::core::ptr::addr_of!((*ptr).prog) as usize - ptr as usize },
+        16usize,
+        concat!(
+            "Offset of field: ",
+            stringify!(lfs_config),
+            "::",
+            stringify!(prog)
+        )
+    );
+    assert_eq!(
+        unsafe { ::core::ptr::addr_of!((*ptr).erase) as usize - ptr as usize },
+        24usize,
+        concat!(
+            "Offset of field: ",
+            stringify!(lfs_config),
+            "::",
+            stringify!(erase)
+        )
+    );
+    assert_eq!(
+        unsafe { ::core::ptr::addr_of!((*ptr).sync) as usize - ptr as usize },
+        32usize,
+        concat!(
+            "Offset of field: ",
+            stringify!(lfs_config),
+            "::",
+            stringify!(sync)
+        )
+    );
+    assert_eq!(
+        unsafe { ::core::ptr::addr_of!((*ptr).read_size) as usize - ptr as usize },
+        40usize,
+        concat!(
+            "Offset of field: ",
+            stringify!(lfs_config),
+            "::",
+            stringify!(read_size)
+        )
+    );
+    assert_eq!(
+        unsafe { ::core::ptr::addr_of!((*ptr).prog_size) as usize - ptr as usize },
+        44usize,
+        concat!(
+            "Offset of field: ",
+            stringify!(lfs_config),
+            "::",
+            stringify!(prog_size)
+        )
+    );
+    assert_eq!(
+        unsafe { ::core::ptr::addr_of!((*ptr).block_size) as usize - ptr as usize },
+        48usize,
+        concat!(
+            "Offset of field: ",
+            stringify!(lfs_config),
+            "::",
+            stringify!(block_size)
+        )
+    );
+    assert_eq!(
+        unsafe { ::core::ptr::addr_of!((*ptr).block_count) as usize - ptr as usize },
+        52usize,
+        concat!(
+            "Offset of field: ",
+            stringify!(lfs_config),
+            "::",
+            stringify!(block_count)
+        )
+    );
+    assert_eq!(
+        unsafe { ::core::ptr::addr_of!((*ptr).lookahead) as usize - ptr as usize },
+        56usize,
+        concat!(
+            "Offset of field: ",
+            stringify!(lfs_config),
+            "::",
+            stringify!(lookahead)
+        )
+    );
+    assert_eq!(
+        unsafe { ::core::ptr::addr_of!((*ptr).read_buffer) as usize - ptr as usize },
+        64usize,
+        concat!(
+            "Offset of field: ",
+            stringify!(lfs_config),
+            "::",
+            stringify!(read_buffer)
+        )
+    );
+    assert_eq!(
+        unsafe { ::core::ptr::addr_of!((*ptr).prog_buffer) as usize - ptr as usize },
+        72usize,
+        concat!(
+            "Offset of field: ",
+            stringify!(lfs_config),
+            "::",
+            stringify!(prog_buffer)
+        )
+    );
+    assert_eq!(
+        unsafe { ::core::ptr::addr_of!((*ptr).lookahead_buffer) as usize - ptr as usize },
+        80usize,
+        concat!(
+            "Offset of field: ",
+            stringify!(lfs_config),
+            "::",
+            stringify!(lookahead_buffer)
+        )
+    );
+    assert_eq!(
+        unsafe { ::core::ptr::addr_of!((*ptr).file_buffer) as usize - ptr as usize },
+        88usize,
+        concat!(
+            "Offset of field: ",
+            stringify!(lfs_config),
+            "::",
+            stringify!(file_buffer)
+        )
+    );
+}
+#[repr(C)]
+#[derive(Debug, Copy, Clone)]
+pub struct lfs_file_config {
+    pub buffer: *mut cty::c_void,
+}
+#[test]
+fn bindgen_test_layout_lfs_file_config() {
+    const UNINIT: ::core::mem::MaybeUninit<lfs_file_config> = ::core::mem::MaybeUninit::uninit();
+    let ptr = UNINIT.as_ptr();
+    assert_eq!(
+        ::core::mem::size_of::<lfs_file_config>(),
+        8usize,
+        concat!("Size of: ", stringify!(lfs_file_config))
+    );
+    assert_eq!(
+        ::core::mem::align_of::<lfs_file_config>(),
+        8usize,
+        concat!("Alignment of ", stringify!(lfs_file_config))
+    );
+    assert_eq!(
+        unsafe { ::core::ptr::addr_of!((*ptr).buffer) as usize - ptr as usize },
+        0usize,
+        concat!(
+            "Offset of field: ",
+            stringify!(lfs_file_config),
+            "::",
+            stringify!(buffer)
+        )
+    );
+}
+#[repr(C)]
+#[derive(Debug, Copy, Clone)]
+pub struct lfs_info {
+    pub type_: u8,
+    pub size: lfs_size_t,
+    pub name: [cty::c_char; 256usize],
+}
+#[test]
+fn bindgen_test_layout_lfs_info() {
+    const UNINIT: ::core::mem::MaybeUninit<lfs_info> = ::core::mem::MaybeUninit::uninit();
+    let ptr = UNINIT.as_ptr();
+    assert_eq!(
+        ::core::mem::size_of::<lfs_info>(),
+        264usize,
+        concat!("Size of: ", stringify!(lfs_info))
+    );
+    assert_eq!(
+        ::core::mem::align_of::<lfs_info>(),
+        4usize,
+        concat!("Alignment of ", stringify!(lfs_info))
+    );
+    assert_eq!(
+        unsafe { ::core::ptr::addr_of!((*ptr).type_) as usize - ptr as usize },
+        0usize,
+        concat!(
+            "Offset of field: ",
+            stringify!(lfs_info),
+            "::",
+            stringify!(type_)
+        )
+    );
+    assert_eq!(
+        unsafe { ::core::ptr::addr_of!((*ptr).size) as usize - ptr as usize },
+        4usize,
+        concat!(
+            "Offset of field: ",
+            stringify!(lfs_info),
+            "::",
+            stringify!(size)
+        )
+    );
+    assert_eq!(
+        unsafe { ::core::ptr::addr_of!((*ptr).name) as usize - ptr as usize },
+        8usize,
+        concat!(
+            "Offset of field: ",
+            stringify!(lfs_info),
+            "::",
+            stringify!(name)
+        )
+    );
+}
+#[repr(C)]
+#[derive(Copy, Clone)]
+pub struct lfs_entry {
+    pub off: lfs_off_t,
+    pub d: lfs_entry_lfs_disk_entry,
+}
+#[test]
+fn bindgen_test_layout_lfs_entry() {
+    const UNINIT: ::core::mem::MaybeUninit<lfs_entry> = ::core::mem::MaybeUninit::uninit();
+    let ptr = UNINIT.as_ptr();
+    assert_eq!(
+        ::core::mem::size_of::<lfs_entry>(),
+        16usize,
+        concat!("Size of: ", stringify!(lfs_entry))
+    );
+    assert_eq!(
+        ::core::mem::align_of::<lfs_entry>(),
+        4usize,
+        concat!("Alignment of ", stringify!(lfs_entry))
+    );
+    assert_eq!(
+        unsafe { ::core::ptr::addr_of!((*ptr).off) as usize - ptr as usize },
+        0usize,
+        concat!(
+            "Offset of field: ",
+            stringify!(lfs_entry),
+            "::",
+            stringify!(off)
+        )
+    );
+    assert_eq!(
+        unsafe { ::core::ptr::addr_of!((*ptr).d) as usize - ptr as usize },
+        4usize,
+        concat!(
+            "Offset of field: ",
+            stringify!(lfs_entry),
+            "::",
+            stringify!(d)
+        )
+    );
+}
+#[repr(C)]
+#[derive(Copy, Clone)]
+pub struct lfs_entry_lfs_disk_entry {
+    pub type_: u8,
+    pub elen: u8,
+    pub alen: u8,
+    pub nlen: u8,
+    pub u: lfs_entry_lfs_disk_entry__bindgen_ty_1,
+}
+#[test]
+fn bindgen_test_layout_lfs_entry_lfs_disk_entry() {
+    const UNINIT: ::core::mem::MaybeUninit<lfs_entry_lfs_disk_entry> =
+        ::core::mem::MaybeUninit::uninit();
+    let ptr = UNINIT.as_ptr();
+    assert_eq!(
+        ::core::mem::size_of::<lfs_entry_lfs_disk_entry>(),
+        12usize,
+        concat!("Size of: ", stringify!(lfs_entry_lfs_disk_entry))
+    );
+    assert_eq!(
+        ::core::mem::align_of::<lfs_entry_lfs_disk_entry>(),
+        4usize,
+        concat!("Alignment of ", stringify!(lfs_entry_lfs_disk_entry))
+    );
+    assert_eq!(
+        unsafe { ::core::ptr::addr_of!((*ptr).type_) as usize - ptr as usize },
+        0usize,
+        concat!(
+            "Offset of field: ",
+            stringify!(lfs_entry_lfs_disk_entry),
+            "::",
+            stringify!(type_)
+        )
+    );
+    assert_eq!(
+        unsafe { ::core::ptr::addr_of!((*ptr).elen) as usize - ptr as usize },
+        1usize,
+        concat!(
+            "Offset of field: ",
+            stringify!(lfs_entry_lfs_disk_entry),
+            "::",
+            stringify!(elen)
+        )
+    );
+    assert_eq!(
+        unsafe { ::core::ptr::addr_of!((*ptr).alen) as usize - ptr as usize },
+        2usize,
+        concat!(
+            "Offset of field: ",
+            stringify!(lfs_entry_lfs_disk_entry),
+            "::",
+            stringify!(alen)
+        )
+    );
+    assert_eq!(
+        unsafe { ::core::ptr::addr_of!((*ptr).nlen) as usize - ptr as usize },
+        3usize,
+        concat!(
+            "Offset of field: ",
+            stringify!(lfs_entry_lfs_disk_entry),
+            "::",
+            stringify!(nlen)
+        )
+    );
+    assert_eq!(
+        unsafe { ::core::ptr::addr_of!((*ptr).u) as usize - ptr as usize },
+        4usize,
+        concat!(
+            "Offset of field: ",
+            stringify!(lfs_entry_lfs_disk_entry),
+            "::",
+            stringify!(u)
+        )
+    );
+}
+#[repr(C)]
+#[derive(Copy, Clone)]
+pub union lfs_entry_lfs_disk_entry__bindgen_ty_1 {
+    pub file: lfs_entry_lfs_disk_entry__bindgen_ty_1__bindgen_ty_1,
+    pub dir: [lfs_block_t; 2usize],
+}
+#[test]
+fn bindgen_test_layout_lfs_entry_lfs_disk_entry__bindgen_ty_1() {
+    const UNINIT: ::core::mem::MaybeUninit<lfs_entry_lfs_disk_entry__bindgen_ty_1> =
+        ::core::mem::MaybeUninit::uninit();
+    let ptr = UNINIT.as_ptr();
+    assert_eq!(
+        ::core::mem::size_of::<lfs_entry_lfs_disk_entry__bindgen_ty_1>(),
+        8usize,
+        concat!(
+            "Size of: ",
+            stringify!(lfs_entry_lfs_disk_entry__bindgen_ty_1)
+        )
+    );
+    assert_eq!(
+        ::core::mem::align_of::<lfs_entry_lfs_disk_entry__bindgen_ty_1>(),
+        4usize,
+        concat!(
+            "Alignment of ",
+            stringify!(lfs_entry_lfs_disk_entry__bindgen_ty_1)
+        )
+    );
+    assert_eq!(
+        unsafe { ::core::ptr::addr_of!((*ptr).file) as usize - ptr as usize },
+        0usize,
+        concat!(
+            "Offset of field: ",
+            stringify!(lfs_entry_lfs_disk_entry__bindgen_ty_1),
+            "::",
+            stringify!(file)
+        )
+    );
+    assert_eq!(
+        unsafe { ::core::ptr::addr_of!((*ptr).dir) as usize - ptr as usize },
+        0usize,
+        concat!(
+            "Offset of field: ",
+            stringify!(lfs_entry_lfs_disk_entry__bindgen_ty_1),
+            "::",
+            stringify!(dir)
+        )
+    );
+}
+#[repr(C)]
+#[derive(Debug, Copy, Clone)]
+pub struct lfs_entry_lfs_disk_entry__bindgen_ty_1__bindgen_ty_1 {
+    pub head: lfs_block_t,
+    pub size: lfs_size_t,
+}
+#[test]
+fn bindgen_test_layout_lfs_entry_lfs_disk_entry__bindgen_ty_1__bindgen_ty_1() {
+    const UNINIT: ::core::mem::MaybeUninit<lfs_entry_lfs_disk_entry__bindgen_ty_1__bindgen_ty_1> =
+        ::core::mem::MaybeUninit::uninit();
+    let ptr = UNINIT.as_ptr();
+    assert_eq!(
+        ::core::mem::size_of::<lfs_entry_lfs_disk_entry__bindgen_ty_1__bindgen_ty_1>(),
+        8usize,
+        concat!(
+            "Size of: ",
+            stringify!(lfs_entry_lfs_disk_entry__bindgen_ty_1__bindgen_ty_1)
+        )
+    );
+    assert_eq!(
+        ::core::mem::align_of::<lfs_entry_lfs_disk_entry__bindgen_ty_1__bindgen_ty_1>(),
+        4usize,
+        concat!(
+            "Alignment of ",
+            stringify!(lfs_entry_lfs_disk_entry__bindgen_ty_1__bindgen_ty_1)
+        )
+    );
+    assert_eq!(
+        unsafe { ::core::ptr::addr_of!((*ptr).head) as usize - ptr as usize },
+        0usize,
+        concat!(
+            "Offset of field: ",
+            stringify!(lfs_entry_lfs_disk_entry__bindgen_ty_1__bindgen_ty_1),
+            "::",
+            stringify!(head)
+        )
+    );
+    assert_eq!(
+        unsafe { ::core::ptr::addr_of!((*ptr).size) as usize - ptr as usize },
+        4usize,
+        concat!(
+            "Offset of field: ",
+            stringify!(lfs_entry_lfs_disk_entry__bindgen_ty_1__bindgen_ty_1),
+            "::",
+            stringify!(size)
+        )
+    );
+}
+pub type lfs_entry_t = lfs_entry;
+#[repr(C)]
+#[derive(Debug, Copy, Clone)]
+pub struct lfs_cache {
+    pub block: lfs_block_t,
+    pub off: lfs_off_t,
+    pub buffer: *mut u8,
+}
+#[test]
+fn bindgen_test_layout_lfs_cache() {
+    const UNINIT: ::core::mem::MaybeUninit<lfs_cache> = ::core::mem::MaybeUninit::uninit();
+    let ptr = UNINIT.as_ptr();
+    assert_eq!(
+        ::core::mem::size_of::<lfs_cache>(),
+        16usize,
+        concat!("Size of: ", stringify!(lfs_cache))
+    );
+    assert_eq!(
+        ::core::mem::align_of::<lfs_cache>(),
+        8usize,
+        concat!("Alignment of ", stringify!(lfs_cache))
+    );
+    assert_eq!(
+        unsafe { ::core::ptr::addr_of!((*ptr).block) as usize - ptr as usize },
+        0usize,
+        concat!(
+            "Offset of field: ",
+            stringify!(lfs_cache),
+            "::",
+            stringify!(block)
+        )
+    );
+    assert_eq!(
+        unsafe { ::core::ptr::addr_of!((*ptr).off) as usize - ptr as usize },
+        4usize,
+        concat!(
+            "Offset of field: ",
+            stringify!(lfs_cache),
+            "::",
+            stringify!(off)
+        )
+    );
+    assert_eq!(
+        unsafe { ::core::ptr::addr_of!((*ptr).buffer) as usize - ptr as usize },
+        8usize,
+        concat!(
+            "Offset of field: ",
+            stringify!(lfs_cache),
+            "::",
+            stringify!(buffer)
+        )
+    );
+}
+pub type lfs_cache_t = lfs_cache;
+#[repr(C)]
+#[derive(Debug, Copy, Clone)]
+pub struct lfs_file {
+    pub next: *mut lfs_file,
+    pub pair: [lfs_block_t; 2usize],
+    pub poff: lfs_off_t,
+    pub head: lfs_block_t,
+    pub size: lfs_size_t,
+    pub cfg: *const lfs_file_config,
+    pub flags: u32,
+    pub pos: lfs_off_t,
+    pub block: lfs_block_t,
+    pub off: lfs_off_t,
+    pub cache: lfs_cache_t,
+}
+#[test]
+fn bindgen_test_layout_lfs_file() {
+    const UNINIT: ::core::mem::MaybeUninit<lfs_file> = ::core::mem::MaybeUninit::uninit();
+    let ptr = UNINIT.as_ptr();
+    assert_eq!(
+        ::core::mem::size_of::<lfs_file>(),
+        72usize,
+        concat!("Size of: ", stringify!(lfs_file))
+    );
+    assert_eq!(
+        ::core::mem::align_of::<lfs_file>(),
+        8usize,
+        concat!("Alignment of ", stringify!(lfs_file))
+    );
+    assert_eq!(
+        unsafe { ::core::ptr::addr_of!((*ptr).next) as usize - ptr as usize },
+        0usize,
+        concat!(
+            "Offset of field: ",
+            stringify!(lfs_file),
+            "::",
+            stringify!(next)
+        )
+    );
+    assert_eq!(
+        unsafe { ::core::ptr::addr_of!((*ptr).pair) as usize - ptr as usize },
+        8usize,
+        concat!(
+            "Offset of field: ",
+            stringify!(lfs_file),
+            "::",
+            stringify!(pair)
+        )
+    );
+    assert_eq!(
+        unsafe { ::core::ptr::addr_of!((*ptr).poff) as usize - ptr as usize },
+        16usize,
+        concat!(
+            "Offset of field: ",
+            stringify!(lfs_file),
+            "::",
+            stringify!(poff)
+        )
+    );
+    assert_eq!(
+        unsafe { ::core::ptr::addr_of!((*ptr).head) as usize - ptr as usize },
+        20usize,
+        concat!(
+            "Offset of field: ",
+            stringify!(lfs_file),
+            "::",
+            stringify!(head)
+        )
+    );
+    assert_eq!(
+        unsafe { ::core::ptr::addr_of!((*ptr).size) as usize - ptr as usize },
+        24usize,
+        concat!(
+            "Offset of field: ",
+            stringify!(lfs_file),
+            "::",
+            stringify!(size)
+        )
+    );
+    assert_eq!(
+        unsafe { ::core::ptr::addr_of!((*ptr).cfg) as usize - ptr as usize },
+        32usize,
+        concat!(
+            "Offset of field: ",
+            stringify!(lfs_file),
+            "::",
+            stringify!(cfg)
+        )
+    );
+    assert_eq!(
+        unsafe { ::core::ptr::addr_of!((*ptr).flags) as usize - ptr as usize },
+        40usize,
+        concat!(
+            "Offset of field: ",
+            stringify!(lfs_file),
+            "::",
+            stringify!(flags)
+        )
+    );
+    assert_eq!(
+        unsafe { ::core::ptr::addr_of!((*ptr).pos) as usize - ptr as usize },
+        44usize,
+        concat!(
+            "Offset of field: ",
+            stringify!(lfs_file),
+            "::",
+            stringify!(pos)
+        )
+    );
+    assert_eq!(
+        unsafe { ::core::ptr::addr_of!((*ptr).block) as usize - ptr as usize },
+        48usize,
+        concat!(
+            "Offset of field: ",
+            stringify!(lfs_file),
+            "::",
+            stringify!(block)
+        )
+    );
+    assert_eq!(
+        unsafe { ::core::ptr::addr_of!((*ptr).off) as usize - ptr as usize },
+        52usize,
+        concat!(
+            "Offset of field: ",
+            stringify!(lfs_file),
+            "::",
+            stringify!(off)
+        )
+    );
+    assert_eq!(
+        unsafe { ::core::ptr::addr_of!((*ptr).cache) as usize - ptr as usize },
+        56usize,
+        concat!(
+            "Offset of field: ",
+            stringify!(lfs_file),
+            "::",
+            stringify!(cache)
+        )
+    );
+}
+pub type lfs_file_t = lfs_file;
+#[repr(C)]
+#[derive(Debug, Copy, Clone)]
+pub struct lfs_dir {
+    pub next: *mut lfs_dir,
+    pub pair: [lfs_block_t; 2usize],
+    pub off: lfs_off_t,
+    pub head: [lfs_block_t; 2usize],
+    pub pos: lfs_off_t,
+    pub d: lfs_dir_lfs_disk_dir,
+}
+#[test]
+fn bindgen_test_layout_lfs_dir() {
+    const UNINIT: ::core::mem::MaybeUninit<lfs_dir> = ::core::mem::MaybeUninit::uninit();
+    let ptr = UNINIT.as_ptr();
+    assert_eq!(
+        ::core::mem::size_of::<lfs_dir>(),
+        48usize,
+        concat!("Size of: ", stringify!(lfs_dir))
+    );
+    assert_eq!(
+        ::core::mem::align_of::<lfs_dir>(),
+        8usize,
+        concat!("Alignment of ", stringify!(lfs_dir))
+    );
+    assert_eq!(
+        unsafe { ::core::ptr::addr_of!((*ptr).next) as usize - ptr as usize },
+        0usize,
+        concat!(
+            "Offset of field: ",
+            stringify!(lfs_dir),
+            "::",
+            stringify!(next)
+        )
+    );
+    assert_eq!(
+        unsafe { ::core::ptr::addr_of!((*ptr).pair) as usize - ptr as usize },
+        8usize,
+        concat!(
+            "Offset of field: ",
+            stringify!(lfs_dir),
+            "::",
+            stringify!(pair)
+        )
+    );
+    assert_eq!(
+        unsafe { ::core::ptr::addr_of!((*ptr).off) as usize - ptr as usize },
+        16usize,
+        concat!(
+            "Offset of field: ",
+            stringify!(lfs_dir),
+            "::",
+            stringify!(off)
+        )
+    );
+    assert_eq!(
+        unsafe { ::core::ptr::addr_of!((*ptr).head) as usize - ptr as usize },
+        20usize,
+        concat!(
+            "Offset of field: ",
+            stringify!(lfs_dir),
+            "::",
+            stringify!(head)
+        )
+    );
+    assert_eq!(
+        unsafe { ::core::ptr::addr_of!((*ptr).pos) as usize - ptr as usize },
+        28usize,
+        concat!(
+            "Offset of field: ",
+            stringify!(lfs_dir),
+            "::",
+            stringify!(pos)
+        )
+    );
+    assert_eq!(
+        unsafe { ::core::ptr::addr_of!((*ptr).d) as usize - ptr as usize },
+        32usize,
+        concat!(
+            "Offset of field: ",
+            stringify!(lfs_dir),
+            "::",
+            stringify!(d)
+        )
+    );
+}
+#[repr(C)]
+#[derive(Debug, Copy, Clone)]
+pub struct lfs_dir_lfs_disk_dir {
+    pub rev: u32,
+    pub size: lfs_size_t,
+    pub tail: [lfs_block_t; 2usize],
+}
+#[test]
+fn bindgen_test_layout_lfs_dir_lfs_disk_dir() {
+    const UNINIT: ::core::mem::MaybeUninit<lfs_dir_lfs_disk_dir> =
+        ::core::mem::MaybeUninit::uninit();
+    let ptr = UNINIT.as_ptr();
+    assert_eq!(
+        ::core::mem::size_of::<lfs_dir_lfs_disk_dir>(),
+        16usize,
+        concat!("Size of: ", stringify!(lfs_dir_lfs_disk_dir))
+    );
+    assert_eq!(
+        ::core::mem::align_of::<lfs_dir_lfs_disk_dir>(),
+        4usize,
+        concat!("Alignment of ", stringify!(lfs_dir_lfs_disk_dir))
+    );
+    assert_eq!(
+        unsafe { ::core::ptr::addr_of!((*ptr).rev) as usize - ptr as usize },
+        0usize,
+        concat!(
+            "Offset of field: ",
+            stringify!(lfs_dir_lfs_disk_dir),
+            "::",
+            stringify!(rev)
+        )
+    );
+    assert_eq!(
+        unsafe { ::core::ptr::addr_of!((*ptr).size) as usize - ptr as usize },
+        4usize,
+        concat!(
+            "Offset of field: ",
+            stringify!(lfs_dir_lfs_disk_dir),
+            "::",
+            stringify!(size)
+        )
+    );
+    assert_eq!(
+        unsafe { ::core::ptr::addr_of!((*ptr).tail) as usize - ptr as usize },
+        8usize,
+        concat!(
+            "Offset of field: ",
+            stringify!(lfs_dir_lfs_disk_dir),
+            "::",
+            stringify!(tail)
+        )
+    );
+}
+pub type lfs_dir_t = lfs_dir;
+#[repr(C)]
+#[derive(Debug, Copy, Clone)]
+pub struct lfs_superblock {
+    pub off: lfs_off_t,
+    pub d: lfs_superblock_lfs_disk_superblock,
+}
+#[test]
+fn bindgen_test_layout_lfs_superblock() {
+    const UNINIT: ::core::mem::MaybeUninit<lfs_superblock> = ::core::mem::MaybeUninit::uninit();
+    let ptr = UNINIT.as_ptr();
+    assert_eq!(
+        ::core::mem::size_of::<lfs_superblock>(),
+        36usize,
+        concat!("Size of: ", stringify!(lfs_superblock))
+    );
+    assert_eq!(
+        ::core::mem::align_of::<lfs_superblock>(),
+        4usize,
+        concat!("Alignment of ", stringify!(lfs_superblock))
+    );
+    assert_eq!(
+        unsafe { ::core::ptr::addr_of!((*ptr).off) as usize - ptr as usize },
+        0usize,
+        concat!(
+            "Offset of field: ",
+            stringify!(lfs_superblock),
+            "::",
+            stringify!(off)
+        )
+    );
+    assert_eq!(
+        unsafe { ::core::ptr::addr_of!((*ptr).d) as usize - ptr as usize },
+        4usize,
+        concat!(
+            "Offset of field: ",
+            stringify!(lfs_superblock),
+            "::",
+            stringify!(d)
+        )
+    );
+}
+#[repr(C)]
+#[derive(Debug, Copy, Clone)]
+pub struct lfs_superblock_lfs_disk_superblock {
+    pub type_: u8,
+    pub elen: u8,
+    pub alen: u8,
+    pub nlen: u8,
+    pub root: [lfs_block_t; 2usize],
+    pub block_size: u32,
+    pub block_count: u32,
+    pub version: u32,
+    pub magic: [cty::c_char; 8usize],
+}
+#[test]
+fn bindgen_test_layout_lfs_superblock_lfs_disk_superblock() {
+    const UNINIT: ::core::mem::MaybeUninit<lfs_superblock_lfs_disk_superblock> =
+        ::core::mem::MaybeUninit::uninit();
+    let ptr = UNINIT.as_ptr();
+    assert_eq!(
+        ::core::mem::size_of::<lfs_superblock_lfs_disk_superblock>(),
+        32usize,
+        concat!("Size of: ", stringify!(lfs_superblock_lfs_disk_superblock))
+    );
+    assert_eq!(
+        ::core::mem::align_of::<lfs_superblock_lfs_disk_superblock>(),
+        4usize,
+        concat!(
+            "Alignment of ",
+            stringify!(lfs_superblock_lfs_disk_superblock)
+        )
+    );
+    assert_eq!(
+        unsafe { ::core::ptr::addr_of!((*ptr).type_) as usize - ptr as usize },
+        0usize,
+        concat!(
+            "Offset of field: ",
+            stringify!(lfs_superblock_lfs_disk_superblock),
+            "::",
+            stringify!(type_)
+        )
+    );
+    assert_eq!(
+        unsafe { ::core::ptr::addr_of!((*ptr).elen) as usize - ptr as usize },
+        1usize,
+        concat!(
+            "Offset of field: ",
+            stringify!(lfs_superblock_lfs_disk_superblock),
+            "::",
+            stringify!(elen)
+        )
+    );
+    assert_eq!(
+        unsafe { ::core::ptr::addr_of!((*ptr).alen) as usize - ptr as usize },
+        2usize,
+        concat!(
+            "Offset of field: ",
+            stringify!(lfs_superblock_lfs_disk_superblock),
+            "::",
+            stringify!(alen)
+        )
+    );
+    assert_eq!(
+        unsafe { ::core::ptr::addr_of!((*ptr).nlen) as usize - ptr as usize },
+        3usize,
+        concat!(
+            "Offset of field: ",
+            stringify!(lfs_superblock_lfs_disk_superblock),
+            "::",
+            stringify!(nlen)
+        )
+    );
+    assert_eq!(
+        unsafe { ::core::ptr::addr_of!((*ptr).root) as usize - ptr as usize },
+        4usize,
+        concat!(
+            "Offset of field: ",
+            stringify!(lfs_superblock_lfs_disk_superblock),
+            "::",
+            stringify!(root)
+        )
+    );
+    assert_eq!(
+        unsafe { ::core::ptr::addr_of!((*ptr).block_size) as usize - ptr as usize },
+        12usize,
+        concat!(
+            "Offset of field: ",
+            stringify!(lfs_superblock_lfs_disk_superblock),
+            "::",
+            stringify!(block_size)
+        )
+    );
+    assert_eq!(
+        unsafe { ::core::ptr::addr_of!((*ptr).block_count) as usize - ptr as usize },
+        16usize,
+        concat!(
+            "Offset of field: ",
+            stringify!(lfs_superblock_lfs_disk_superblock),
+            "::",
+            stringify!(block_count)
+        )
+    );
+    assert_eq!(
+        unsafe { ::core::ptr::addr_of!((*ptr).version) as usize - ptr as usize },
+        20usize,
+        concat!(
+            "Offset of field: ",
+            stringify!(lfs_superblock_lfs_disk_superblock),
+            "::",
+            stringify!(version)
+        )
+    );
+    assert_eq!(
+        unsafe { ::core::ptr::addr_of!((*ptr).magic) as usize - ptr as usize },
+        24usize,
+        concat!(
+            "Offset of field: ",
+            stringify!(lfs_superblock_lfs_disk_superblock),
+            "::",
+            stringify!(magic)
+        )
+    );
+}
+pub type lfs_superblock_t = lfs_superblock;
+#[repr(C)]
+#[derive(Debug, Copy, Clone)]
+pub struct lfs_free {
+    pub off: lfs_block_t,
+    pub size: lfs_block_t,
+    pub i: lfs_block_t,
+    pub ack: lfs_block_t,
+    pub buffer: *mut u32,
+}
+#[test]
+fn bindgen_test_layout_lfs_free() {
+    const UNINIT: ::core::mem::MaybeUninit<lfs_free> = ::core::mem::MaybeUninit::uninit();
+    let ptr = UNINIT.as_ptr();
+    assert_eq!(
+        ::core::mem::size_of::<lfs_free>(),
+        24usize,
+        concat!("Size of: ", stringify!(lfs_free))
+    );
+    assert_eq!(
+        ::core::mem::align_of::<lfs_free>(),
+        8usize,
+        concat!("Alignment of ", stringify!(lfs_free))
+    );
+    assert_eq!(
+        unsafe { ::core::ptr::addr_of!((*ptr).off) as usize - ptr as usize },
+        0usize,
+        concat!(
+            "Offset of field: ",
+            stringify!(lfs_free),
+            "::",
+            stringify!(off)
+        )
+    );
+    assert_eq!(
+        unsafe { ::core::ptr::addr_of!((*ptr).size) as usize - ptr as usize },
+        4usize,
+        concat!(
+            "Offset of field: ",
+            stringify!(lfs_free),
+            "::",
+            stringify!(size)
+        )
+    );
+    assert_eq!(
+        unsafe { ::core::ptr::addr_of!((*ptr).i) as usize - ptr as usize },
+        8usize,
+        concat!(
+            "Offset of field: ",
+            stringify!(lfs_free),
+            "::",
+            stringify!(i)
+        )
+    );
+    assert_eq!(
+        unsafe { ::core::ptr::addr_of!((*ptr).ack) as usize - ptr as usize },
+        12usize,
+        concat!(
+            "Offset of field: ",
+            stringify!(lfs_free),
+            "::",
+            stringify!(ack)
+        )
+    );
+    assert_eq!(
+        unsafe { ::core::ptr::addr_of!((*ptr).buffer) as usize - ptr as usize },
+        16usize,
+        concat!(
+            "Offset of field: ",
+            stringify!(lfs_free),
+            "::",
+            stringify!(buffer)
+        )
+    );
+}
+pub type lfs_free_t = lfs_free;
+#[repr(C)]
+#[derive(Debug, Copy, Clone)]
+pub struct lfs {
+    pub cfg: *const lfs_config,
+    pub root: [lfs_block_t; 2usize],
+    pub files: *mut lfs_file_t,
+    pub dirs: *mut lfs_dir_t,
+    pub rcache: lfs_cache_t,
+    pub pcache: lfs_cache_t,
+    pub free: lfs_free_t,
+    pub deorphaned: bool,
+    pub moving: bool,
+}
+#[test]
+fn bindgen_test_layout_lfs() {
+    const UNINIT: ::core::mem::MaybeUninit<lfs> = ::core::mem::MaybeUninit::uninit();
+    let ptr = UNINIT.as_ptr();
+    assert_eq!(
+        ::core::mem::size_of::<lfs>(),
+        96usize,
+        concat!("Size of: ", stringify!(lfs))
+    );
+    assert_eq!(
+        ::core::mem::align_of::<lfs>(),
+        8usize,
+        concat!("Alignment of ", stringify!(lfs))
+    );
+    assert_eq!(
+        unsafe { ::core::ptr::addr_of!((*ptr).cfg) as usize - ptr as usize },
+        0usize,
+        concat!("Offset of field: ", stringify!(lfs), "::", stringify!(cfg))
+    );
+    assert_eq!(
+        unsafe { ::core::ptr::addr_of!((*ptr).root) as usize - ptr as usize },
+        8usize,
+        concat!("Offset of field: ", stringify!(lfs), "::", stringify!(root))
+    );
+    assert_eq!(
+        unsafe { ::core::ptr::addr_of!((*ptr).files) as usize - ptr as usize },
+        16usize,
+        concat!(
+            "Offset of field: ",
+            stringify!(lfs),
+            "::",
+            stringify!(files)
+        )
+    );
+    assert_eq!(
+        unsafe { ::core::ptr::addr_of!((*ptr).dirs) as usize - ptr as usize },
+        24usize,
+        concat!("Offset of field: ", stringify!(lfs), "::", stringify!(dirs))
+    );
+    assert_eq!(
+        unsafe { ::core::ptr::addr_of!((*ptr).rcache) as usize - ptr as usize },
+        32usize,
+        concat!(
+            "Offset of field: ",
+            stringify!(lfs),
+            "::",
+            stringify!(rcache)
+        )
+    );
+    assert_eq!(
+        unsafe { ::core::ptr::addr_of!((*ptr).pcache) as usize - ptr as usize },
+        48usize,
+        concat!(
+            "Offset of field: ",
+            stringify!(lfs),
+            "::",
+            stringify!(pcache)
+        )
+    );
+    assert_eq!(
+        unsafe { ::core::ptr::addr_of!((*ptr).free) as usize - ptr as usize },
+        64usize,
+        concat!("Offset of field: ", stringify!(lfs), "::", stringify!(free))
+    );
+    assert_eq!(
+        unsafe { ::core::ptr::addr_of!((*ptr).deorphaned) as usize - ptr as usize },
+        88usize,
+        concat!(
+            "Offset of field: ",
+            stringify!(lfs),
+            "::",
+            stringify!(deorphaned)
+        )
+    );
+    assert_eq!(
+        unsafe { ::core::ptr::addr_of!((*ptr).moving) as usize - ptr as usize },
+        89usize,
+        concat!(
+            "Offset of field: ",
+            stringify!(lfs),
+            "::",
+            stringify!(moving)
+        )
+    );
+}
+pub type lfs_t = lfs;
+extern "C" {
+    pub fn lfs_format(lfs: *mut lfs_t, config: *const lfs_config) -> cty::c_int;
+}
+extern "C" {
+    pub fn lfs_mount(lfs: *mut lfs_t, config: *const lfs_config) -> cty::c_int;
+}
+extern "C" {
+    pub fn lfs_unmount(lfs: *mut lfs_t) -> cty::c_int;
+}
+extern "C" {
+    pub fn lfs_remove(lfs: *mut lfs_t, path: *const cty::c_char) -> cty::c_int;
+}
+extern "C" {
+    pub fn lfs_rename(
+        lfs: *mut lfs_t,
+        oldpath: *const cty::c_char,
+        newpath: *const cty::c_char,
+    ) -> cty::c_int;
+}
+extern "C" {
+    pub fn lfs_stat(lfs: *mut lfs_t, path: *const cty::c_char, info: *mut lfs_info)
+        -> cty::c_int;
+}
+extern "C" {
+    pub fn lfs_file_open(
+        lfs: *mut lfs_t,
+        file: *mut lfs_file_t,
+        path: *const cty::c_char,
+        flags: cty::c_int,
+    ) -> cty::c_int;
+}
+extern "C" {
+    pub fn lfs_file_opencfg(
+        lfs: *mut lfs_t,
+        file: *mut lfs_file_t,
+        path: *const cty::c_char,
+        flags: cty::c_int,
+        config: *const lfs_file_config,
+    ) -> cty::c_int;
+}
+extern "C" {
+    pub fn lfs_file_close(lfs: *mut lfs_t, file: *mut lfs_file_t) -> cty::c_int;
+}
+extern "C" {
+    pub fn lfs_file_sync(lfs: *mut lfs_t, file: *mut lfs_file_t) -> cty::c_int;
+}
+extern "C" {
+    pub fn lfs_file_read(
+        lfs: *mut lfs_t,
+        file: *mut lfs_file_t,
+        buffer: *mut cty::c_void,
+        size: lfs_size_t,
+    ) -> lfs_ssize_t;
+}
+extern "C" {
+    pub fn lfs_file_write(
+        lfs: *mut lfs_t,
+        file: *mut lfs_file_t,
+        buffer: *const cty::c_void,
+        size: lfs_size_t,
+    ) -> lfs_ssize_t;
+}
+extern "C" {
+    pub fn lfs_file_seek(
+        lfs: *mut lfs_t,
+        file: *mut lfs_file_t,
+        off: lfs_soff_t,
+        whence: cty::c_int,
+    ) -> lfs_soff_t;
+}
+extern "C" {
+    pub fn lfs_file_truncate(lfs: *mut lfs_t, file: *mut lfs_file_t, size: lfs_off_t)
+        -> cty::c_int;
+}
+extern "C" {
+    pub fn lfs_file_tell(lfs: *mut lfs_t, file: *mut lfs_file_t) -> lfs_soff_t;
+}
+extern "C" {
+    pub fn lfs_file_rewind(lfs: *mut lfs_t, file: *mut lfs_file_t) -> cty::c_int;
+}
+extern "C" {
+    pub fn lfs_file_size(lfs: *mut lfs_t, file: *mut lfs_file_t) -> lfs_soff_t;
+}
+extern "C" {
+    pub fn lfs_mkdir(lfs: *mut lfs_t, path: *const cty::c_char) -> cty::c_int;
+}
+extern "C" {
+    pub fn lfs_dir_open(lfs: *mut lfs_t, dir: *mut lfs_dir_t, path: *const cty::c_char)
+        -> cty::c_int;
+}
+extern "C" {
+    pub fn lfs_dir_close(lfs: *mut lfs_t, dir: *mut lfs_dir_t) -> cty::c_int;
+}
+extern "C" {
+    pub fn lfs_dir_read(lfs: *mut lfs_t, dir: *mut lfs_dir_t, info: *mut lfs_info) -> cty::c_int;
+}
+extern "C" {
+    pub fn lfs_dir_seek(lfs: *mut lfs_t, dir: *mut lfs_dir_t, off: lfs_off_t) -> cty::c_int;
+}
+extern "C" {
+    pub fn lfs_dir_tell(lfs: *mut lfs_t, dir: *mut lfs_dir_t) -> lfs_soff_t;
+}
+extern "C" {
+    pub fn lfs_dir_rewind(lfs: *mut lfs_t, dir: *mut lfs_dir_t) -> cty::c_int;
+}
+extern "C" {
+    pub fn lfs_traverse(
+        lfs: *mut lfs_t,
+        cb: ::core::option::Option<
+            unsafe extern "C" fn(arg1: *mut cty::c_void, arg2: lfs_block_t) -> cty::c_int,
+        >,
+        data: *mut cty::c_void,
+    ) -> cty::c_int;
+}
+extern "C" {
+    pub fn lfs_deorphan(lfs: *mut lfs_t) -> cty::c_int;
+}
@@ -3,4 +3,54 @@
 #![allow(non_camel_case_types)]
 #![allow(non_snake_case)]
 
+#[cfg(feature = "alloc")]
+extern crate alloc;
+
+#[cfg(feature = "alloc")]
+mod malloc;
+
+#[cfg(feature = "bindgen")]
 include!(concat!(env!("OUT_DIR"), "/bindings.rs"));
+#[cfg(not(feature = "bindgen"))]
+include!("bindings.rs");
+
+/// Hook receiving log messages of the C library. The format string uses printf syntax and each
+/// of its arguments is passed as a 32-bit integer.
+#[cfg(feature = "rust-util")]
+pub type lfs_rust_log_t = Option<
+    unsafe extern "C" fn(
+        level: cty::c_int,
+        file: *const cty::c_char,
+        line: cty::c_int,
+        fmt: *const cty::c_char,
+        args: *const u32,
+        nargs: usize,
+    ),
+>;
+
+/// Hook receiving failed assertions of the C library; it must not return.
+#[cfg(feature = "rust-util")]
+pub type lfs_rust_assert_t = Option<
+    unsafe extern "C" fn(file: *const cty::c_char, line: cty::c_int, expr: *const cty::c_char),
+>;
+
+/// Hook computing the CRC of littlefs metadata, continuing from the given value.
+#[cfg(feature = "rust-util")]
+pub type lfs_rust_crc_t =
+    Option<unsafe extern "C" fn(crc: u32, buffer: *const cty::c_void, size: usize) -> u32>;
+
+#[cfg(feature = "rust-util")]
+pub const LFS_RUST_LOG_ERROR: cty::c_int = 1;
+#[cfg(feature = "rust-util")]
+pub const LFS_RUST_LOG_WARN: cty::c_int = 2;
+#[cfg(feature = "rust-util")]
+pub const LFS_RUST_LOG_DEBUG: cty::c_int = 4;
+#[cfg(feature = "rust-util")]
+pub const LFS_RUST_LOG_TRACE: cty::c_int = 5;
+
+#[cfg(feature = "rust-util")]
+extern "C" {
+    pub static mut lfs_rust_log_hook: lfs_rust_log_t;
+    pub static mut lfs_rust_assert_hook: lfs_rust_assert_t;
+    pub static mut lfs_rust_crc_hook: lfs_rust_crc_t;
+}
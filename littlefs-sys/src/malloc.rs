@@ -0,0 +1,42 @@
+//! Allocation functions used by the C library when built with the `alloc` feature.
+//!
+//! `lfs_free` is not told the size of the block it releases, so every allocation is prefixed with
+//! a header recording the layout it was made with.
+
+use alloc::alloc::{alloc, dealloc, Layout};
+use core::mem;
+use core::ptr;
+
+/// Alignment of the returned memory, enough for any type littlefs places in its buffers.
+const ALIGN: usize = 2 * mem::size_of::<usize>();
+
+/// Size of the header preceding each allocation; a multiple of the alignment so that the memory
+/// handed out remains aligned.
+const HEADER: usize = ALIGN;
+
+#[no_mangle]
+pub unsafe extern "C" fn lfs_rust_malloc(size: usize) -> *mut cty::c_void {
+    let layout = match size
+        .checked_add(HEADER)
+        .and_then(|total| Layout::from_size_align(total, ALIGN).ok())
+    {
+        Some(layout) => layout,
+        None => return ptr::null_mut(),
+    };
+    let base = alloc(layout);
+    if base.is_null() {
+        return ptr::null_mut();
+    }
+    (base as *mut usize).write(layout.size());
+    base.add(HEADER) as *mut cty::c_void
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn lfs_rust_free(p: *mut cty::c_void) {
+    if p.is_null() {
+        return;
+    }
+    let base = (p as *mut u8).sub(HEADER);
+    let size = (base as *const usize).read();
+    dealloc(base, Layout::from_size_align_unchecked(size, ALIGN));
+}
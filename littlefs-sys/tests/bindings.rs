@@ -0,0 +1,32 @@
+//! Check that the pre-generated bindings match the vendored C headers.
+//!
+//! Only built with the `bindgen` feature, which generates fresh bindings into OUT_DIR. Run with
+//! `UPDATE_BINDINGS=1` to overwrite src/bindings.rs with the freshly generated bindings.
+
+#![cfg(feature = "bindgen")]
+
+use std::env;
+use std::fs;
+use std::path::Path;
+
+const GENERATED: &str = include_str!(concat!(env!("OUT_DIR"), "/bindings.rs"));
+const CHECKED_IN: &str = include_str!("../src/bindings.rs");
+
+/// Strip whitespace so that differences in rustfmt versions do not count as drift.
+fn normalize(bindings: &str) -> String {
+    bindings.chars().filter(|c| !c.is_whitespace()).collect()
+}
+
+#[test]
+fn test_bindings_up_to_date() {
+    if env::var_os("UPDATE_BINDINGS").is_some() {
+        let path = Path::new(env!("CARGO_MANIFEST_DIR")).join("src/bindings.rs");
+        fs::write(path, GENERATED).unwrap();
+        return;
+    }
+    assert!(
+        normalize(GENERATED) == normalize(CHECKED_IN),
+        "src/bindings.rs is out of date with littlefs/lfs.h; \
+         regenerate it with `UPDATE_BINDINGS=1 cargo test --features bindgen`"
+    );
+}
@@ -0,0 +1,416 @@
+//! Atomic replacement of files.
+//!
+//! A file rewritten in place is left half written if power is lost during the update. Instead
+//! [`LittleFs::write_atomic`] and [`AtomicWriter`] write the new contents to a hidden temporary
+//! sibling, named after the target with the [`TEMP_PREFIX`] prepended, and then `rename` it over
+//! the target. littlefs performs the rename in a single commit, so after a power loss the target
+//! holds either its old or its new contents. A temporary left behind by an interrupted update
+//! stays until [`LittleFs::remove_stale_temporaries`] is called, or until the next mount if
+//! `Config::remove_temporaries` is set.
+//!
+//! ```ignore
+//! fs.write_atomic("/etc/config", b"baud=115200\n")?;
+//!
+//! let mut file = File::default();
+//! let mut writer = fs.atomic_writer(&mut file, "/log/summary")?;
+//! writer.write(&mut fs, b"boot\n")?;
+//! writer.commit(&mut fs)?;
+//! ```
+//!
+//! [`LittleFs::write_atomic`]: ../struct.LittleFs.html#method.write_atomic
+//! [`LittleFs::remove_stale_temporaries`]: ../struct.LittleFs.html#method.remove_stale_temporaries
+//! [`AtomicWriter`]: struct.AtomicWriter.html
+//! [`TEMP_PREFIX`]: constant.TEMP_PREFIX.html
+
+use core::convert::TryFrom;
+
+use crate::path::{Path, PathBuf};
+use crate::{Dir, EntryType, File, FileOpenFlags, Filename, FsError, LittleFs, Operation, Storage};
+
+/// Prefix of the name of temporary files. Regular files named by the prefix followed by the name
+/// of their target are removed when the filesystem is mounted.
+pub const TEMP_PREFIX: &str = ".~";
+
+/// File being written to a temporary sibling of its target, which it replaces on `commit`.
+///
+/// The file handle is borrowed for as long as the writer exists and must not move. A writer that
+/// is dropped without `commit` or `abort` leaves the handle open, like a `File` that is not
+/// closed.
+pub struct AtomicWriter<'a, const CACHE_SIZE: usize> {
+    file: &'a mut File<CACHE_SIZE>,
+    path: PathBuf,
+}
+
+impl<T: Storage, const READ_SIZE: usize, const PROG_SIZE: usize, const LOOKAHEAD_SIZE: usize>
+    LittleFs<T, READ_SIZE, PROG_SIZE, LOOKAHEAD_SIZE>
+{
+    /// Replace the contents of the file at `path` by `data`, creating it if needed. Either the
+    /// old or the new contents are visible at any time, also across a power loss.
+    pub fn write_atomic<P: AsRef<Path>>(&mut self, path: P, data: &[u8]) -> Result<(), FsError> {
+        let path = path.as_ref();
+        let mut file = File::default();
+        let res = self.atomic_writer(&mut file, path).and_then(|mut writer| {
+            if let Err(error) = writer.write(self, data) {
+                writer.abort(self);
+                return Err(error);
+            }
+            writer.commit(self)
+        });
+        self.finish_composite(Operation::WriteAtomic, path.as_str(), res)
+    }
+
+    /// Start replacing the file at `path`. The new contents are written through the returned
+    /// writer and become visible once it is committed.
+    pub fn atomic_writer<'a, P: AsRef<Path>>(
+        &mut self,
+        file: &'a mut File<PROG_SIZE>,
+        path: P,
+    ) -> Result<AtomicWriter<'a, PROG_SIZE>, FsError> {
+        let path = PathBuf::try_from(path.as_ref())?;
+        let temp = temp_path(&path)?;
+        let flags = FileOpenFlags::WRONLY | FileOpenFlags::CREAT | FileOpenFlags::TRUNC;
+        self.file_open(file, &temp, flags)?;
+        Ok(AtomicWriter { file, path })
+    }
+
+    /// Remove the temporary files left behind by interrupted atomic updates anywhere in the
+    /// filesystem. This also runs when the filesystem is mounted if `Config::remove_temporaries`
+    /// is set, in which case a failure is only reported through `last_error`.
+    ///
+    /// Every regular file named like the temporaries of `atomic_writer` is removed, including
+    /// files of that name which were not created by it. The tree is
+    /// walked with a single directory handle and path buffer, reading each directory again after
+    /// walking one of its subdirectories, so the walk takes time quadratic in the number of
+    /// directories. Subtrees too deep for the path buffer and entries whose names are not valid
+    /// UTF-8 are skipped.
+    pub fn remove_stale_temporaries(&mut self) -> Result<(), FsError> {
+        let res = self.remove_stale_temporaries_in();
+        self.finish_composite(Operation::RemoveStaleTemporaries, "/", res)
+    }
+
+    fn remove_stale_temporaries_in(&mut self) -> Result<(), FsError> {
+        let mut path: PathBuf = PathBuf::try_from("/")?;
+        let mut dir = Dir::default();
+        // Directory whose subtree was just walked, to continue with its parent after it.
+        let mut resume: Option<Filename> = None;
+        loop {
+            self.dir_open(&mut dir, &path)?;
+            let res = self.clean_dir(&mut dir, &mut path, resume.take());
+            match self.close_dir_after(&mut dir, res)? {
+                Some(name) => {
+                    // clean_dir checked that the name is valid UTF-8 and fits.
+                    path.push(name.as_str().map_err(|_| FsError::Inval)?)?;
+                }
+                None if path.parent().is_none() => return Ok(()),
+                None => {
+                    resume = path.file_name().map(Filename::try_from).transpose()?;
+                    path.pop();
+                }
+            }
+        }
+    }
+
+    /// Remove the temporary files of the open directory `dir` at `path` and return the next
+    /// subdirectory to walk, if any. Entries up to and including `resume` are skipped.
+    fn clean_dir(
+        &mut self,
+        dir: &mut Dir,
+        path: &mut PathBuf,
+        mut resume: Option<Filename>,
+    ) -> Result<Option<Filename>, FsError> {
+        while let Some(info) = self.dir_read(dir)? {
+            if let Some(ref name) = resume {
+                if info.name == *name {
+                    resume = None;
+                }
+                continue;
+            }
+            let name = match info.name.as_str() {
+                Ok(".") | Ok("..") | Err(_) => continue,
+                Ok(name) => name,
+            };
+            if path.push(name).is_err() {
+                continue;
+            }
+            let res = match info.entry_type {
+                EntryType::Directory => Ok(true),
+                // littlefs moves open directories past removed entries.
+                EntryType::RegularFile if is_temp_name(name) => self.remove(&*path).map(|()| false),
+                EntryType::RegularFile => Ok(false),
+            };
+            path.pop();
+            if res? {
+                return Ok(Some(info.name));
+            }
+        }
+        Ok(None)
+    }
+}
+
+impl<'a, const CACHE_SIZE: usize> AtomicWriter<'a, CACHE_SIZE> {
+    /// Append data to the new contents.
+    pub fn write<T: Storage, const READ_SIZE: usize, const LOOKAHEAD_SIZE: usize>(
+        &mut self,
+        fs: &mut LittleFs<T, READ_SIZE, CACHE_SIZE, LOOKAHEAD_SIZE>,
+        buf: &[u8],
+    ) -> Result<usize, FsError> {
+        fs.file_write(self.file, buf)
+    }
+
+    /// Sync the new contents to storage and replace the target with them. On failure the target
+    /// is left unchanged and the temporary file is removed.
+    pub fn commit<T: Storage, const READ_SIZE: usize, const LOOKAHEAD_SIZE: usize>(
+        self,
+        fs: &mut LittleFs<T, READ_SIZE, CACHE_SIZE, LOOKAHEAD_SIZE>,
+    ) -> Result<(), FsError> {
+        let temp = temp_path(&self.path)?;
+        let res = fs.file_sync(self.file);
+        let closed = fs.file_close(self.file);
+        let res = res.and(closed).and_then(|()| fs.rename(&temp, &self.path));
+        if res.is_err() {
+            let last_error = fs.last_error;
+            let _ = fs.remove(&temp);
+            fs.last_error = last_error;
+        }
+        res
+    }
+
+    /// Discard the new contents, leaving the target unchanged.
+    pub fn abort<T: Storage, const READ_SIZE: usize, const LOOKAHEAD_SIZE: usize>(
+        self,
+        fs: &mut LittleFs<T, READ_SIZE, CACHE_SIZE, LOOKAHEAD_SIZE>,
+    ) {
+        let last_error = fs.last_error;
+        let _ = fs.file_close(self.file);
+        if let Ok(temp) = temp_path(&self.path) {
+            let _ = fs.remove(&temp);
+        }
+        fs.last_error = last_error;
+    }
+}
+
+/// Whether `name` is the name of a temporary sibling as made by `temp_path`.
+fn is_temp_name(name: &str) -> bool {
+    name.len() > TEMP_PREFIX.len() && name.starts_with(TEMP_PREFIX)
+}
+
+/// Path of the temporary sibling of `path`.
+fn temp_path(path: &Path) -> Result<PathBuf, FsError> {
+    let name = path.file_name().ok_or(FsError::Inval)?;
+    let mut temp = PathBuf::try_from(path.parent().ok_or(FsError::Inval)?)?;
+    temp.push(TEMP_PREFIX)?;
+    temp.push_str(name)?;
+    Ok(temp)
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use crate::tests::RamStorage;
+    use crate::{Config, Info};
+    use core::cell::Cell;
+
+    /// Storage which loses power after a number of program and erase operations: every
+    /// operation after that fails without changing the storage.
+    struct PowerCutStorage<'a> {
+        ram: RamStorage,
+        budget: &'a Cell<Option<usize>>,
+    }
+
+    impl<'a> PowerCutStorage<'a> {
+        fn spend(&self) -> Result<(), FsError> {
+            match self.budget.get() {
+                Some(0) => Err(FsError::Io),
+                Some(budget) => {
+                    self.budget.set(Some(budget - 1));
+                    Ok(())
+                }
+                None => Ok(()),
+            }
+        }
+    }
+
+    impl<'a> Storage for PowerCutStorage<'a> {
+        fn read(&self, off: usize, buf: &mut [u8]) -> Result<usize, FsError> {
+            self.ram.read(off, buf)
+        }
+
+        fn write(&mut self, off: usize, data: &[u8]) -> Result<usize, FsError> {
+            self.spend()?;
+            self.ram.write(off, data)
+        }
+
+        fn erase(&mut self, off: usize, len: usize) -> Result<usize, FsError> {
+            self.spend()?;
+            self.ram.erase(off, len)
+        }
+    }
+
+    /// Configuration removing stale temporaries when mounting.
+    fn cleaning() -> Config {
+        Config {
+            remove_temporaries: true,
+            ..Config::default()
+        }
+    }
+
+    fn exists<S: Storage>(lfs: &mut LittleFs<S>, path: &str) -> bool {
+        let mut info = Info::default();
+        lfs.stat(path, &mut info).is_ok()
+    }
+
+    #[test]
+    fn test_temp_path() {
+        assert_eq!(
+            temp_path(Path::new("/etc/config")).unwrap(),
+            "/etc/.~config"
+        );
+        assert_eq!(temp_path(Path::new("/config")).unwrap(), "/.~config");
+        assert_eq!(temp_path(Path::new("config")).unwrap(), ".~config");
+        assert_eq!(temp_path(Path::new("/")), Err(FsError::Inval));
+    }
+
+    #[test]
+    fn test_write_atomic() {
+        let mut lfs = LittleFs::new(RamStorage::default());
+        lfs.format().unwrap();
+        let mut lfs = lfs.mount().unwrap();
+        let mut buf = [0u8; 16];
+
+        lfs.write_atomic("/config", b"old").unwrap();
+        lfs.write_atomic("/config", b"new").unwrap();
+        let len = lfs.read_into("/config", &mut buf).unwrap();
+        assert_eq!(&buf[..len], b"new");
+        assert!(!exists(&mut lfs, "/.~config"));
+
+        let mut file = File::default();
+        let mut writer = lfs.atomic_writer(&mut file, "/config").unwrap();
+        writer.write(&mut lfs, b"discarded").unwrap();
+        writer.abort(&mut lfs);
+        let mut writer = lfs.atomic_writer(&mut file, "/config").unwrap();
+        writer.write(&mut lfs, b"stream").unwrap();
+        let len = lfs.read_into("/config", &mut buf).unwrap();
+        assert_eq!(&buf[..len], b"new");
+        writer.write(&mut lfs, b"ed").unwrap();
+        writer.commit(&mut lfs).unwrap();
+        let len = lfs.read_into("/config", &mut buf).unwrap();
+        assert_eq!(&buf[..len], b"streamed");
+        assert!(!exists(&mut lfs, "/.~config"));
+
+        assert_eq!(
+            lfs.write_atomic("/missing/config", b""),
+            Err(FsError::Noent)
+        );
+        assert_eq!(lfs.last_error().unwrap().operation(), Operation::FileOpen);
+        lfs.unmount().unwrap();
+    }
+
+    #[test]
+    fn test_stale_temporaries_removed_at_mount() {
+        let mut lfs = LittleFs::new(RamStorage::default());
+        lfs.format().unwrap();
+        let mut lfs = lfs.mount().unwrap();
+        lfs.create_dir_all("/a/b/c").unwrap();
+        lfs.mkdir("/a/d").unwrap();
+        lfs.mkdir("/.~dir").unwrap();
+        let paths = [
+            "/.~x",
+            "/a/.~x",
+            "/a/b/c/.~x",
+            "/a/d/.~x",
+            "/a/d/keep",
+            "/a/.~",
+        ];
+        for path in &paths {
+            lfs.write(path, b"data").unwrap();
+        }
+        let storage = lfs.unmount().unwrap();
+
+        // Mounting leaves the temporaries for an explicit call by default.
+        let mut lfs = LittleFs::new(storage).mount().unwrap();
+        assert!(exists(&mut lfs, "/a/b/c/.~x"));
+        let storage = lfs.unmount().unwrap();
+
+        let mut lfs = LittleFs::with_config(storage, cleaning()).mount().unwrap();
+        assert!(lfs.last_error().is_none());
+        for path in &["/.~x", "/a/.~x", "/a/b/c/.~x", "/a/d/.~x"] {
+            assert!(!exists(&mut lfs, path));
+        }
+        assert!(exists(&mut lfs, "/a/d/keep"));
+        assert!(exists(&mut lfs, "/a/.~"));
+        assert!(exists(&mut lfs, "/.~dir"));
+        assert!(exists(&mut lfs, "/a/b/c"));
+        lfs.unmount().unwrap();
+    }
+
+    #[test]
+    fn test_stale_temporaries_error() {
+        let budget = Cell::new(None);
+        let mut lfs = LittleFs::new(PowerCutStorage {
+            ram: RamStorage::default(),
+            budget: &budget,
+        });
+        lfs.format().unwrap();
+        let mut lfs = lfs.mount().unwrap();
+        lfs.write("/.~config", b"stale").unwrap();
+        let storage = lfs.unmount().unwrap();
+
+        // The mount succeeds and reports the failed removal.
+        budget.set(Some(0));
+        let mut lfs = LittleFs::with_config(storage, cleaning()).mount().unwrap();
+        let context = lfs.last_error().unwrap();
+        assert_eq!(context.error(), FsError::Io);
+        assert_eq!(context.operation(), Operation::Remove);
+        assert_eq!(lfs.remove_stale_temporaries(), Err(FsError::Io));
+
+        budget.set(None);
+        lfs.remove_stale_temporaries().unwrap();
+        assert!(!exists(&mut lfs, "/.~config"));
+        lfs.unmount().unwrap();
+    }
+
+    #[test]
+    fn test_write_atomic_power_cut() {
+        let budget = Cell::new(None);
+        let mut lfs = LittleFs::new(PowerCutStorage {
+            ram: RamStorage::default(),
+            budget: &budget,
+        });
+        lfs.format().unwrap();
+        let mut lfs = lfs.mount().unwrap();
+        lfs.write("/config", b"old contents").unwrap();
+        let image = lfs.unmount().unwrap().ram;
+
+        // Larger than the file cache, so that the data takes several programs.
+        let new = [b'n'; 600];
+        let mut buf = [0u8; 600];
+        for cut in 0.. {
+            let mut lfs = LittleFs::new(PowerCutStorage {
+                ram: image.clone(),
+                budget: &budget,
+            })
+            .mount()
+            .unwrap();
+            budget.set(Some(cut));
+            let completed = lfs.write_atomic("/config", &new).is_ok();
+            let storage = lfs.into_inner();
+            budget.set(None);
+
+            let mut lfs = LittleFs::with_config(storage, cleaning()).mount().unwrap();
+            let len = lfs.read_into("/config", &mut buf).unwrap();
+            assert!(
+                &buf[..len] == b"old contents" || buf[..len] == new[..],
+                "torn contents after {} operations",
+                cut
+            );
+            assert!(!exists(&mut lfs, "/.~config"));
+            lfs.unmount().unwrap();
+            if completed {
+                assert_eq!(&buf[..len], &new[..]);
+                assert!(cut > 0);
+                break;
+            }
+        }
+    }
+}
@@ -0,0 +1,188 @@
+//! Reading and writing whole files.
+//!
+//! These helpers open the file, transfer its contents and close it again, also when the transfer
+//! fails, so that no handle is left open behind an error. On failure `last_error` reports the call
+//! that failed rather than the close that followed it.
+//!
+//! ```ignore
+//! let mut buf = [0u8; 64];
+//! let len = fs.read_into("/etc/config", &mut buf)?;
+//! fs.write("/etc/config", b"baud=115200\n")?;
+//! fs.append("/log.txt", b"boot\n")?;
+//! ```
+
+#[cfg(feature = "alloc")]
+use alloc::{string::String, vec, vec::Vec};
+
+use crate::path::Path;
+use crate::{File, FileOpenFlags, FsError, LittleFs, Operation, Storage};
+
+impl<T: Storage, const READ_SIZE: usize, const PROG_SIZE: usize, const LOOKAHEAD_SIZE: usize>
+    LittleFs<T, READ_SIZE, PROG_SIZE, LOOKAHEAD_SIZE>
+{
+    /// Read a whole file into `buf`, returning its length. Fails with `FBig` if the file is
+    /// larger than `buf`.
+    pub fn read_into<P: AsRef<Path>>(&mut self, path: P, buf: &mut [u8]) -> Result<usize, FsError> {
+        let path = path.as_ref();
+        self.with_file(
+            Operation::ReadInto,
+            path,
+            FileOpenFlags::RDONLY,
+            |fs, file| {
+                let size = fs.file_size(file)?;
+                if size > buf.len() {
+                    return Err(FsError::FBig);
+                }
+                fs.read_exact(file, &mut buf[..size])?;
+                Ok(size)
+            },
+        )
+    }
+
+    /// Read a whole file into a vector.
+    #[cfg(feature = "alloc")]
+    pub fn read<P: AsRef<Path>>(&mut self, path: P) -> Result<Vec<u8>, FsError> {
+        let path = path.as_ref();
+        self.with_file(Operation::Read, path, FileOpenFlags::RDONLY, |fs, file| {
+            let mut data = vec![0; fs.file_size(file)?];
+            fs.read_exact(file, &mut data)?;
+            Ok(data)
+        })
+    }
+
+    /// Read a whole file into a string. Fails with `Inval` if the file is not valid UTF-8.
+    #[cfg(feature = "alloc")]
+    pub fn read_to_string<P: AsRef<Path>>(&mut self, path: P) -> Result<String, FsError> {
+        let path = path.as_ref();
+        let res = String::from_utf8(self.read(path)?).map_err(|_| FsError::Inval);
+        self.finish_composite(Operation::ReadToString, path.as_str(), res)
+    }
+
+    /// Write `data` to a file, creating it if needed and replacing its previous contents.
+    pub fn write<P: AsRef<Path>>(&mut self, path: P, data: &[u8]) -> Result<(), FsError> {
+        let flags = FileOpenFlags::WRONLY | FileOpenFlags::CREAT | FileOpenFlags::TRUNC;
+        self.with_file(Operation::Write, path.as_ref(), flags, |fs, file| {
+            fs.file_write(file, data)?;
+            Ok(())
+        })
+    }
+
+    /// Append `data` to a file, creating it if needed.
+    pub fn append<P: AsRef<Path>>(&mut self, path: P, data: &[u8]) -> Result<(), FsError> {
+        let flags = FileOpenFlags::WRONLY | FileOpenFlags::CREAT | FileOpenFlags::APPEND;
+        self.with_file(Operation::Append, path.as_ref(), flags, |fs, file| {
+            fs.file_write(file, data)?;
+            Ok(())
+        })
+    }
+
+    /// Open the file at `path`, run `f` on it and close it again. If `f` fails its error is
+    /// returned and the file is closed regardless.
+    fn with_file<R, F>(
+        &mut self,
+        operation: Operation,
+        path: &Path,
+        flags: FileOpenFlags,
+        f: F,
+    ) -> Result<R, FsError>
+    where
+        F: FnOnce(&mut Self, &mut File<PROG_SIZE>) -> Result<R, FsError>,
+    {
+        let mut file = File::default();
+        self.file_open(&mut file, path, flags)?;
+        let res = f(self, &mut file);
+        let res = self.close_file_after(&mut file, res);
+        self.finish_composite(operation, path.as_str(), res)
+    }
+
+    /// Close `file` after a transfer with result `res`. If the transfer failed its error and
+    /// context are kept and the close is only attempted.
+    pub(crate) fn close_file_after<R>(
+        &mut self,
+        file: &mut File<PROG_SIZE>,
+        res: Result<R, FsError>,
+    ) -> Result<R, FsError> {
+        let last_error = self.last_error;
+        let closed = self.file_close(file);
+        match res {
+            Ok(value) => closed.map(|()| value),
+            Err(error) => {
+                self.last_error = last_error;
+                Err(error)
+            }
+        }
+    }
+
+    /// Fill `buf` from the current position of `file`.
+    fn read_exact(&mut self, file: &mut File<PROG_SIZE>, buf: &mut [u8]) -> Result<(), FsError> {
+        let mut pos = 0;
+        while pos < buf.len() {
+            match self.file_read(file, &mut buf[pos..])? {
+                // The file was truncated below its reported size.
+                0 => return Err(FsError::Corrupt),
+                len => pos += len,
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use crate::tests::RamStorage;
+
+    #[test]
+    fn test_read_write() {
+        let mut lfs = LittleFs::new(RamStorage::default());
+        lfs.format().unwrap();
+        let mut lfs = lfs.mount().unwrap();
+
+        lfs.write("/config", b"mode=auto\n").unwrap();
+        lfs.append("/config", b"baud=9600\n").unwrap();
+        let mut buf = [0u8; 32];
+        let len = lfs.read_into("/config", &mut buf).unwrap();
+        assert_eq!(&buf[..len], b"mode=auto\nbaud=9600\n");
+
+        lfs.write("/config", b"mode=off\n").unwrap();
+        let len = lfs.read_into("/config", &mut buf).unwrap();
+        assert_eq!(&buf[..len], b"mode=off\n");
+
+        assert_eq!(lfs.read_into("/config", &mut buf[..4]), Err(FsError::FBig));
+        let context = lfs.last_error().unwrap();
+        assert_eq!(context.operation(), Operation::ReadInto);
+        #[cfg(feature = "error-path")]
+        assert_eq!(context.path(), Some("/config"));
+        assert_eq!(lfs.read_into("/missing", &mut buf), Err(FsError::Noent));
+        assert_eq!(lfs.last_error().unwrap().operation(), Operation::FileOpen);
+        assert_eq!(lfs.append("/missing/file", b""), Err(FsError::Noent));
+
+        // Every handle was closed, so the directory holding the file can be removed.
+        lfs.mkdir("/dir").unwrap();
+        lfs.write("/dir/file", b"data").unwrap();
+        assert_eq!(
+            lfs.read_into("/dir/file", &mut buf[..1]),
+            Err(FsError::FBig)
+        );
+        lfs.remove("/dir/file").unwrap();
+        lfs.remove("/dir").unwrap();
+        lfs.unmount().unwrap();
+    }
+
+    #[cfg(feature = "alloc")]
+    #[test]
+    fn test_read_alloc() {
+        let mut lfs = LittleFs::new(RamStorage::default());
+        lfs.format().unwrap();
+        let mut lfs = lfs.mount().unwrap();
+
+        let data: Vec<u8> = (0..10_000).map(|i| i as u8).collect();
+        lfs.write("/data", &data).unwrap();
+        assert_eq!(lfs.read("/data").unwrap(), data);
+        assert_eq!(lfs.read_to_string("/data"), Err(FsError::Inval));
+        lfs.write("/text", "grüße".as_bytes()).unwrap();
+        assert_eq!(lfs.read_to_string("/text").unwrap(), "grüße");
+        lfs.unmount().unwrap();
+    }
+}
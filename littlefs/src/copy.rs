@@ -0,0 +1,627 @@
+//! Copying files and directory trees, within a filesystem or between two filesystems.
+//!
+//! Data is moved through a bounce buffer, either one provided by the caller or an internal one of
+//! [`COPY_BUFFER_SIZE`] bytes on the stack. The `_with` variants take the buffer and a callback
+//! which is told the [`Progress`] of the current file after every chunk:
+//!
+//! ```ignore
+//! let mut buf = [0u8; 1024];
+//! littlefs::copy::copy_dir_all_between_with(
+//!     &mut internal,
+//!     &mut external,
+//!     "/log",
+//!     "/backup/log",
+//!     &mut buf,
+//!     |progress| log::info!("{}: {}/{}", progress.path, progress.copied, progress.size),
+//! )?;
+//! ```
+//!
+//! Copies of a directory walk the source tree with a single directory handle and a path buffer
+//! for each side. Existing destination files are replaced. On failure `last_error` of the
+//! filesystem whose call failed names the entry.
+//!
+//! [`COPY_BUFFER_SIZE`]: constant.COPY_BUFFER_SIZE.html
+//! [`Progress`]: struct.Progress.html
+
+use core::convert::TryFrom;
+
+use crate::path::{Path, PathBuf};
+use crate::{Dir, EntryType, File, FileOpenFlags, Filename, FsError, Info, LittleFs, Operation};
+use crate::{Storage, NAME_MAX_LEN};
+
+/// Size of the bounce buffer used when the caller does not provide one.
+pub const COPY_BUFFER_SIZE: usize = 256;
+
+/// Progress of the file being copied, reported after every chunk.
+#[derive(Debug, Clone, Copy)]
+pub struct Progress<'a> {
+    /// Source path of the file.
+    pub path: &'a Path,
+    /// Number of bytes of the file copied so far.
+    pub copied: usize,
+    /// Size of the file in bytes.
+    pub size: usize,
+}
+
+impl<T: Storage, const READ_SIZE: usize, const PROG_SIZE: usize, const LOOKAHEAD_SIZE: usize>
+    LittleFs<T, READ_SIZE, PROG_SIZE, LOOKAHEAD_SIZE>
+{
+    /// Copy the file at `src` to `dst`, returning the number of bytes copied.
+    pub fn copy<P: AsRef<Path>, Q: AsRef<Path>>(
+        &mut self,
+        src: P,
+        dst: Q,
+    ) -> Result<usize, FsError> {
+        self.copy_with(src, dst, &mut [0; COPY_BUFFER_SIZE], |_| ())
+    }
+
+    /// Copy the file at `src` to `dst` through `buf`, reporting progress to `progress`. `dst`
+    /// may not be `src`.
+    pub fn copy_with<P, Q, F>(
+        &mut self,
+        src: P,
+        dst: Q,
+        buf: &mut [u8],
+        mut progress: F,
+    ) -> Result<usize, FsError>
+    where
+        P: AsRef<Path>,
+        Q: AsRef<Path>,
+        F: FnMut(&Progress),
+    {
+        let (src, dst) = (src.as_ref(), dst.as_ref());
+        let res = match (
+            src.normalize::<NAME_MAX_LEN>(),
+            dst.normalize::<NAME_MAX_LEN>(),
+        ) {
+            (Ok(a), Ok(b)) if a == b => Err(FsError::Inval),
+            _ => Ends::copy_file(self, src, dst, buf, &mut progress),
+        };
+        self.finish_composite(Operation::Copy, src.as_str(), res)
+    }
+
+    /// Copy the directory `src` and everything below it to `dst`, creating `dst` and its
+    /// parents as needed. Returns the number of bytes copied.
+    pub fn copy_dir_all<P: AsRef<Path>, Q: AsRef<Path>>(
+        &mut self,
+        src: P,
+        dst: Q,
+    ) -> Result<usize, FsError> {
+        self.copy_dir_all_with(src, dst, &mut [0; COPY_BUFFER_SIZE], |_| ())
+    }
+
+    /// Copy the directory `src` and everything below it to `dst` through `buf`, reporting the
+    /// progress of each file to `progress`. `dst` may not lie within `src`.
+    pub fn copy_dir_all_with<P, Q, F>(
+        &mut self,
+        src: P,
+        dst: Q,
+        buf: &mut [u8],
+        mut progress: F,
+    ) -> Result<usize, FsError>
+    where
+        P: AsRef<Path>,
+        Q: AsRef<Path>,
+        F: FnMut(&Progress),
+    {
+        let src = src.as_ref();
+        let res = Ends::copy_tree(self, src, dst.as_ref(), buf, &mut progress);
+        self.finish_composite(Operation::CopyDirAll, src.as_str(), res)
+    }
+}
+
+/// Copy the file at `src` on `from` to `dst` on `to`, returning the number of bytes copied.
+pub fn copy_between<
+    T,
+    U,
+    P,
+    Q,
+    const R1: usize,
+    const P1: usize,
+    const L1: usize,
+    const R2: usize,
+    const P2: usize,
+    const L2: usize,
+>(
+    from: &mut LittleFs<T, R1, P1, L1>,
+    to: &mut LittleFs<U, R2, P2, L2>,
+    src: P,
+    dst: Q,
+) -> Result<usize, FsError>
+where
+    T: Storage,
+    U: Storage,
+    P: AsRef<Path>,
+    Q: AsRef<Path>,
+{
+    copy_between_with(from, to, src, dst, &mut [0; COPY_BUFFER_SIZE], |_| ())
+}
+
+/// Copy the file at `src` on `from` to `dst` on `to` through `buf`, reporting progress to
+/// `progress`. On failure `last_error` of `from` describes the copy, unless a call to `to`
+/// failed and is described by its `last_error`.
+pub fn copy_between_with<
+    T,
+    U,
+    P,
+    Q,
+    F,
+    const R1: usize,
+    const P1: usize,
+    const L1: usize,
+    const R2: usize,
+    const P2: usize,
+    const L2: usize,
+>(
+    from: &mut LittleFs<T, R1, P1, L1>,
+    to: &mut LittleFs<U, R2, P2, L2>,
+    src: P,
+    dst: Q,
+    buf: &mut [u8],
+    mut progress: F,
+) -> Result<usize, FsError>
+where
+    T: Storage,
+    U: Storage,
+    P: AsRef<Path>,
+    Q: AsRef<Path>,
+    F: FnMut(&Progress),
+{
+    let (src, dst) = (src.as_ref(), dst.as_ref());
+    from.last_error = None;
+    to.last_error = None;
+    let res = Ends::copy_file(&mut (&mut *from, &mut *to), src, dst, buf, &mut progress);
+    if res.is_err() && to.last_error.is_some() {
+        return res;
+    }
+    from.finish_composite(Operation::Copy, src.as_str(), res)
+}
+
+/// Copy the directory `src` on `from` and everything below it to `dst` on `to`, creating `dst`
+/// and its parents as needed. Returns the number of bytes copied.
+pub fn copy_dir_all_between<
+    T,
+    U,
+    P,
+    Q,
+    const R1: usize,
+    const P1: usize,
+    const L1: usize,
+    const R2: usize,
+    const P2: usize,
+    const L2: usize,
+>(
+    from: &mut LittleFs<T, R1, P1, L1>,
+    to: &mut LittleFs<U, R2, P2, L2>,
+    src: P,
+    dst: Q,
+) -> Result<usize, FsError>
+where
+    T: Storage,
+    U: Storage,
+    P: AsRef<Path>,
+    Q: AsRef<Path>,
+{
+    copy_dir_all_between_with(from, to, src, dst, &mut [0; COPY_BUFFER_SIZE], |_| ())
+}
+
+/// Copy the directory `src` on `from` and everything below it to `dst` on `to` through `buf`,
+/// reporting the progress of each file to `progress`. Failures are recorded as by
+/// [`copy_between_with`].
+///
+/// [`copy_between_with`]: fn.copy_between_with.html
+pub fn copy_dir_all_between_with<
+    T,
+    U,
+    P,
+    Q,
+    F,
+    const R1: usize,
+    const P1: usize,
+    const L1: usize,
+    const R2: usize,
+    const P2: usize,
+    const L2: usize,
+>(
+    from: &mut LittleFs<T, R1, P1, L1>,
+    to: &mut LittleFs<U, R2, P2, L2>,
+    src: P,
+    dst: Q,
+    buf: &mut [u8],
+    mut progress: F,
+) -> Result<usize, FsError>
+where
+    T: Storage,
+    U: Storage,
+    P: AsRef<Path>,
+    Q: AsRef<Path>,
+    F: FnMut(&Progress),
+{
+    let (src, dst) = (src.as_ref(), dst.as_ref());
+    from.last_error = None;
+    to.last_error = None;
+    let res = Ends::copy_tree(&mut (&mut *from, &mut *to), src, dst, buf, &mut progress);
+    if res.is_err() && to.last_error.is_some() {
+        return res;
+    }
+    from.finish_composite(Operation::CopyDirAll, src.as_str(), res)
+}
+
+/// Source and destination filesystems of a copy, implemented by a single filesystem for copies
+/// within it and by a pair of filesystems for copies between them.
+trait Ends<
+    T: Storage,
+    U: Storage,
+    const R1: usize,
+    const P1: usize,
+    const L1: usize,
+    const R2: usize,
+    const P2: usize,
+    const L2: usize,
+>
+{
+    /// Whether source and destination are the same filesystem.
+    const SAME: bool;
+
+    fn src(&mut self) -> &mut LittleFs<T, R1, P1, L1>;
+
+    fn dst(&mut self) -> &mut LittleFs<U, R2, P2, L2>;
+
+    fn copy_file<F: FnMut(&Progress)>(
+        &mut self,
+        src: &Path,
+        dst: &Path,
+        buf: &mut [u8],
+        progress: &mut F,
+    ) -> Result<usize, FsError> {
+        if buf.is_empty() {
+            return Err(FsError::Inval);
+        }
+        let mut src_file: File<P1> = File::default();
+        self.src()
+            .file_open(&mut src_file, src, FileOpenFlags::RDONLY)?;
+        let res = self.copy_from(&mut src_file, src, dst, buf, progress);
+        self.src().close_file_after(&mut src_file, res)
+    }
+
+    /// Copy the contents of the open file `src_file` at `src` to `dst`.
+    fn copy_from<F: FnMut(&Progress)>(
+        &mut self,
+        src_file: &mut File<P1>,
+        src: &Path,
+        dst: &Path,
+        buf: &mut [u8],
+        progress: &mut F,
+    ) -> Result<usize, FsError> {
+        let size = self.src().file_size(src_file)?;
+        let mut dst_file: File<P2> = File::default();
+        let flags = FileOpenFlags::WRONLY | FileOpenFlags::CREAT | FileOpenFlags::TRUNC;
+        self.dst().file_open(&mut dst_file, dst, flags)?;
+        let mut copied = 0;
+        let res = loop {
+            let len = match self.src().file_read(src_file, buf) {
+                Ok(0) => break Ok(copied),
+                Ok(len) => len,
+                Err(error) => break Err(error),
+            };
+            if let Err(error) = self.dst().file_write(&mut dst_file, &buf[..len]) {
+                break Err(error);
+            }
+            copied += len;
+            progress(&Progress {
+                path: src,
+                copied,
+                size,
+            });
+        };
+        if res.is_ok() && size == 0 {
+            progress(&Progress {
+                path: src,
+                copied,
+                size,
+            });
+        }
+        self.dst().close_file_after(&mut dst_file, res)
+    }
+
+    fn copy_tree<F: FnMut(&Progress)>(
+        &mut self,
+        src: &Path,
+        dst: &Path,
+        buf: &mut [u8],
+        progress: &mut F,
+    ) -> Result<usize, FsError> {
+        let mut src_path: PathBuf = src.normalize()?;
+        let mut dst_path: PathBuf = dst.normalize()?;
+        if Self::SAME && dst_path.starts_with(&src_path) {
+            return Err(FsError::Inval);
+        }
+        let mut info = Info::default();
+        self.src().stat(&src_path, &mut info)?;
+        if info.entry_type != EntryType::Directory {
+            return Err(FsError::NotDir);
+        }
+        self.dst().create_dir_all(&dst_path)?;
+
+        let mut dir = Dir::default();
+        let mut depth = 0;
+        let mut total = 0;
+        // Position after the last entry read from the current directory, or the name of the
+        // subdirectory just copied when returning to its parent.
+        let mut pos = None;
+        let mut resume = None;
+        loop {
+            let entry = self.next_entry(&mut dir, &src_path, &mut pos, resume.take())?;
+            match entry {
+                Some(info) => {
+                    let name = info.name.as_str().map_err(|_| FsError::Inval)?;
+                    src_path.push(name)?;
+                    dst_path.push(name)?;
+                    match info.entry_type {
+                        EntryType::Directory => {
+                            self.dst().create_dir_all(&dst_path)?;
+                            depth += 1;
+                            pos = None;
+                        }
+                        EntryType::RegularFile => {
+                            total += self.copy_file(&src_path, &dst_path, buf, progress)?;
+                            src_path.pop();
+                            dst_path.pop();
+                        }
+                    }
+                }
+                None if depth == 0 => return Ok(total),
+                None => {
+                    resume = src_path.file_name().map(Filename::try_from).transpose()?;
+                    src_path.pop();
+                    dst_path.pop();
+                    depth -= 1;
+                    pos = None;
+                }
+            }
+        }
+    }
+
+    /// Read the entry of the source directory at `path` following position `pos`, or
+    /// following the entry named `resume`, or else the first one. `.` and `..` are skipped.
+    /// The directory is closed again before returning so that it can be changed by the copy.
+    fn next_entry(
+        &mut self,
+        dir: &mut Dir,
+        path: &Path,
+        pos: &mut Option<usize>,
+        resume: Option<Filename>,
+    ) -> Result<Option<Info>, FsError> {
+        let fs = self.src();
+        fs.dir_open(dir, path)?;
+        let res = read_next(fs, dir, pos, resume);
+        fs.close_dir_after(dir, res)
+    }
+}
+
+impl<T: Storage, const R: usize, const P: usize, const L: usize> Ends<T, T, R, P, L, R, P, L>
+    for LittleFs<T, R, P, L>
+{
+    const SAME: bool = true;
+
+    fn src(&mut self) -> &mut LittleFs<T, R, P, L> {
+        self
+    }
+
+    fn dst(&mut self) -> &mut LittleFs<T, R, P, L> {
+        self
+    }
+}
+
+impl<
+        T: Storage,
+        U: Storage,
+        const R1: usize,
+        const P1: usize,
+        const L1: usize,
+        const R2: usize,
+        const P2: usize,
+        const L2: usize,
+    > Ends<T, U, R1, P1, L1, R2, P2, L2>
+    for (&mut LittleFs<T, R1, P1, L1>, &mut LittleFs<U, R2, P2, L2>)
+{
+    const SAME: bool = false;
+
+    fn src(&mut self) -> &mut LittleFs<T, R1, P1, L1> {
+        self.0
+    }
+
+    fn dst(&mut self) -> &mut LittleFs<U, R2, P2, L2> {
+        self.1
+    }
+}
+
+/// Read the entry of the open directory `dir` following position `pos`, or following the entry
+/// named `resume`, or else the first one, skipping `.` and `..`.
+fn read_next<T: Storage, const R: usize, const P: usize, const L: usize>(
+    fs: &mut LittleFs<T, R, P, L>,
+    dir: &mut Dir,
+    pos: &mut Option<usize>,
+    mut resume: Option<Filename>,
+) -> Result<Option<Info>, FsError> {
+    if let Some(pos) = *pos {
+        fs.dir_seek(dir, pos as isize)?;
+    }
+    while let Some(info) = fs.dir_read(dir)? {
+        if let Some(ref name) = resume {
+            if info.name == *name {
+                resume = None;
+            }
+            continue;
+        }
+        if info.name != "." && info.name != ".." {
+            *pos = Some(fs.dir_tell(dir)?);
+            return Ok(Some(info));
+        }
+    }
+    Ok(None)
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use crate::tests::RamStorage;
+
+    extern crate std;
+    use std::vec::Vec;
+
+    fn mounted() -> LittleFs<RamStorage> {
+        let mut lfs = LittleFs::new(RamStorage::default());
+        lfs.format().unwrap();
+        lfs.mount().unwrap()
+    }
+
+    fn contents(lfs: &mut LittleFs<RamStorage>, path: &str) -> Vec<u8> {
+        let mut buf = [0u8; 8192];
+        let len = lfs.read_into(path, &mut buf).unwrap();
+        buf[..len].to_vec()
+    }
+
+    #[test]
+    fn test_copy() {
+        let mut lfs = mounted();
+        let data: Vec<u8> = (0..5000).map(|i| i as u8).collect();
+        lfs.write("/data", &data).unwrap();
+        lfs.write("/backup", b"stale").unwrap();
+
+        let mut reports = Vec::new();
+        let copied = lfs
+            .copy_with("/data", "/backup", &mut [0; 1024], |progress| {
+                assert_eq!(progress.path, "/data");
+                assert_eq!(progress.size, 5000);
+                reports.push(progress.copied);
+            })
+            .unwrap();
+        assert_eq!(copied, 5000);
+        assert_eq!(reports, [1024, 2048, 3072, 4096, 5000]);
+        assert_eq!(contents(&mut lfs, "/backup"), data);
+        assert_eq!(contents(&mut lfs, "/data"), data);
+
+        // Empty files are reported once.
+        lfs.write("/empty", b"").unwrap();
+        let mut reports = Vec::new();
+        let copied = lfs
+            .copy_with("/empty", "/empty.bak", &mut [0; 16], |progress| {
+                reports.push((progress.copied, progress.size))
+            })
+            .unwrap();
+        assert_eq!(copied, 0);
+        assert_eq!(reports, [(0, 0)]);
+
+        assert_eq!(lfs.copy("/missing", "/copy"), Err(FsError::Noent));
+        assert_eq!(
+            lfs.copy_with("/data", "/copy", &mut [], |_| ()),
+            Err(FsError::Inval)
+        );
+        assert_eq!(lfs.copy("/data", "//data/."), Err(FsError::Inval));
+        lfs.unmount().unwrap();
+    }
+
+    #[test]
+    fn test_copy_dir_all() {
+        let mut lfs = mounted();
+        lfs.create_dir_all("/log/old").unwrap();
+        lfs.mkdir("/log/empty").unwrap();
+        lfs.write("/log/0", b"zero").unwrap();
+        lfs.write("/log/old/1", b"one").unwrap();
+        lfs.write("/log/old/2", b"two").unwrap();
+        lfs.write("/log/3", b"three").unwrap();
+
+        assert_eq!(lfs.copy_dir_all("/log", "/backup/log").unwrap(), 15);
+        assert_eq!(contents(&mut lfs, "/backup/log/0"), b"zero");
+        assert_eq!(contents(&mut lfs, "/backup/log/old/1"), b"one");
+        assert_eq!(contents(&mut lfs, "/backup/log/old/2"), b"two");
+        assert_eq!(contents(&mut lfs, "/backup/log/3"), b"three");
+        lfs.remove("/backup/log/empty").unwrap();
+
+        assert_eq!(lfs.copy_dir_all("/log", "/log/copy"), Err(FsError::Inval));
+        assert_eq!(lfs.copy_dir_all("/log/0", "/copy"), Err(FsError::NotDir));
+        lfs.unmount().unwrap();
+    }
+
+    #[test]
+    fn test_copy_between() {
+        let mut internal = mounted();
+        let mut external =
+            LittleFs::<RamStorage, 64, 128, 8, crate::Unmounted>::new_sized(RamStorage::default());
+        external.format().unwrap();
+        let mut external = external.mount().unwrap();
+
+        internal.create_dir_all("/log/old").unwrap();
+        internal.write("/log/0", b"zero").unwrap();
+        internal.write("/log/old/1", b"one").unwrap();
+        internal.write("/config", b"mode=auto").unwrap();
+
+        assert_eq!(
+            copy_between(&mut internal, &mut external, "/config", "/config").unwrap(),
+            9
+        );
+        let mut files = 0;
+        let copied = copy_dir_all_between_with(
+            &mut internal,
+            &mut external,
+            "/log",
+            "/",
+            &mut [0; 2],
+            |progress| {
+                if progress.copied == progress.size {
+                    files += 1;
+                }
+            },
+        )
+        .unwrap();
+        assert_eq!((copied, files), (7, 2));
+
+        let mut buf = [0u8; 16];
+        let len = external.read_into("/config", &mut buf).unwrap();
+        assert_eq!(&buf[..len], b"mode=auto");
+        let len = external.read_into("/old/1", &mut buf).unwrap();
+        assert_eq!(&buf[..len], b"one");
+        assert_eq!(
+            copy_between(&mut internal, &mut external, "/missing", "/x"),
+            Err(FsError::Noent)
+        );
+        assert_eq!(
+            internal.last_error().unwrap().operation(),
+            Operation::FileOpen
+        );
+
+        // Failures of the copy itself are recorded on the source, failures of the destination
+        // on the destination only.
+        assert_eq!(
+            copy_between_with(
+                &mut internal,
+                &mut external,
+                "/config",
+                "/x",
+                &mut [],
+                |_| ()
+            ),
+            Err(FsError::Inval)
+        );
+        assert_eq!(internal.last_error().unwrap().operation(), Operation::Copy);
+        assert_eq!(
+            copy_dir_all_between(&mut internal, &mut external, "/config", "/x"),
+            Err(FsError::NotDir)
+        );
+        assert_eq!(
+            internal.last_error().unwrap().operation(),
+            Operation::CopyDirAll
+        );
+        assert_eq!(
+            copy_between(&mut internal, &mut external, "/config", "/missing/x"),
+            Err(FsError::Noent)
+        );
+        assert!(internal.last_error().is_none());
+        assert_eq!(
+            external.last_error().unwrap().operation(),
+            Operation::FileOpen
+        );
+    }
+}
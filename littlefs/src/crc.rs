@@ -0,0 +1,169 @@
+//! CRC computation for littlefs metadata.
+//!
+//! littlefs protects its metadata with a CRC-32 using the polynomial 0x04c11db7 in its reflected
+//! form, starting from 0xffffffff and without a final inversion. By default the C library computes
+//! it in software; [`set_crc`] replaces that routine with any [`Crc`] implementation, for example
+//! one driving a CRC peripheral. Every implementation must produce the same values so that images
+//! remain readable by the others.
+//!
+//! The C library calls a single CRC routine without telling it which filesystem it works for, so
+//! the implementation is installed once for the whole program rather than per filesystem. It is
+//! shared by every filesystem, possibly from several threads, and must therefore be `Sync`; a
+//! peripheral is typically kept behind a critical section mutex. Install it at startup, before
+//! any filesystem is formatted or mounted:
+//!
+//! ```ignore
+//! static CRC: PeripheralCrc = PeripheralCrc::new();
+//!
+//! littlefs::crc::set_crc(&CRC)?;
+//! ```
+//!
+//! [`set_crc`]: fn.set_crc.html
+
+use core::slice;
+#[cfg(target_has_atomic = "ptr")]
+use core::sync::atomic::{AtomicPtr, AtomicU8, AtomicUsize, Ordering};
+
+#[cfg(target_has_atomic = "ptr")]
+use littlefs_sys as lfs;
+
+#[cfg(target_has_atomic = "ptr")]
+use crate::FsError;
+
+/// Routine continuing the littlefs CRC-32 over a buffer.
+pub trait Crc: Sync {
+    /// Continue the CRC `crc` over `data` and return the updated value.
+    fn update(&self, crc: u32, data: &[u8]) -> u32;
+}
+
+/// Software implementation processing four bits at a time, matching the C library.
+pub struct SoftwareCrc;
+
+impl Crc for SoftwareCrc {
+    fn update(&self, crc: u32, data: &[u8]) -> u32 {
+        const RTABLE: [u32; 16] = [
+            0x00000000, 0x1db71064, 0x3b6e20c8, 0x26d930ac, 0x76dc4190, 0x6b6b51f4, 0x4db26158,
+            0x5005713c, 0xedb88320, 0xf00f9344, 0xd6d6a3e8, 0xcb61b38c, 0x9b64c2b0, 0x86d3d2d4,
+            0xa00ae278, 0xbdbdf21c,
+        ];
+
+        let mut crc = crc;
+        for &byte in data {
+            crc = (crc >> 4) ^ RTABLE[((crc ^ u32::from(byte)) & 0xf) as usize];
+            crc = (crc >> 4) ^ RTABLE[((crc ^ u32::from(byte >> 4)) & 0xf) as usize];
+        }
+        crc
+    }
+}
+
+#[cfg(target_has_atomic = "ptr")]
+const UNSET: u8 = 0;
+#[cfg(target_has_atomic = "ptr")]
+const INSTALLING: u8 = 1;
+#[cfg(target_has_atomic = "ptr")]
+const SET: u8 = 2;
+
+/// Whether an implementation has been installed.
+#[cfg(target_has_atomic = "ptr")]
+static STATE: AtomicU8 = AtomicU8::new(UNSET);
+/// Installed implementation, read by its hook.
+#[cfg(target_has_atomic = "ptr")]
+static CONTEXT: AtomicPtr<()> = AtomicPtr::new(core::ptr::null_mut());
+/// Address of the hook of the installed implementation, telling implementations at the same
+/// address apart.
+#[cfg(target_has_atomic = "ptr")]
+static HOOK: AtomicUsize = AtomicUsize::new(0);
+
+/// Compute the CRC of metadata of every filesystem with `crc` instead of the software routine
+/// of the C library.
+///
+/// The implementation can be installed only once. Installing the same instance again succeeds,
+/// while installing another one fails with `Exist` and leaves the first in place. Install it
+/// before any filesystem is formatted or mounted, as the C library reads the routine without
+/// synchronization.
+#[cfg(target_has_atomic = "ptr")]
+pub fn set_crc<C: Crc>(crc: &'static C) -> Result<(), FsError> {
+    let context = crc as *const C as *mut ();
+    let hook = crc_hook::<C> as *const () as usize;
+    match STATE.compare_exchange(UNSET, INSTALLING, Ordering::Acquire, Ordering::Acquire) {
+        Ok(_) => {
+            CONTEXT.store(context, Ordering::Release);
+            HOOK.store(hook, Ordering::Relaxed);
+            unsafe {
+                lfs::lfs_rust_crc_hook = Some(crc_hook::<C>);
+            }
+            STATE.store(SET, Ordering::Release);
+            Ok(())
+        }
+        Err(SET)
+            if CONTEXT.load(Ordering::Relaxed) == context
+                && HOOK.load(Ordering::Relaxed) == hook =>
+        {
+            Ok(())
+        }
+        Err(_) => Err(FsError::Exist),
+    }
+}
+
+/// C callback forwarding CRC computation to the installed implementation of type `C`.
+#[cfg(target_has_atomic = "ptr")]
+unsafe extern "C" fn crc_hook<C: Crc>(crc: u32, buffer: *const cty::c_void, size: usize) -> u32 {
+    if size == 0 {
+        return crc;
+    }
+    let data = slice::from_raw_parts(buffer as *const u8, size);
+    let context = CONTEXT.load(Ordering::Acquire) as *const C;
+    if context.is_null() {
+        // The hook was seen before the implementation it calls; both compute the same values.
+        return SoftwareCrc.update(crc, data);
+    }
+    (*context).update(crc, data)
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use core::sync::atomic::{AtomicUsize, Ordering};
+
+    /// Alternate implementation processing one bit at a time, counting its calls like a driver
+    /// keeping the state of a peripheral. Installing it is tested in `tests/crc.rs`, which runs
+    /// in a process of its own.
+    struct BitwiseCrc {
+        calls: AtomicUsize,
+    }
+
+    impl Crc for BitwiseCrc {
+        fn update(&self, crc: u32, data: &[u8]) -> u32 {
+            self.calls.fetch_add(1, Ordering::Relaxed);
+            let mut crc = crc;
+            for &byte in data {
+                crc ^= u32::from(byte);
+                for _ in 0..8 {
+                    crc = if crc & 1 != 0 {
+                        (crc >> 1) ^ 0xedb88320
+                    } else {
+                        crc >> 1
+                    };
+                }
+            }
+            crc
+        }
+    }
+
+    #[test]
+    fn test_crc_check_value() {
+        let check = b"123456789";
+        let bitwise = BitwiseCrc {
+            calls: AtomicUsize::new(0),
+        };
+        assert_eq!(
+            SoftwareCrc.update(0xffffffff, check) ^ 0xffffffff,
+            0xcbf43926
+        );
+        assert_eq!(bitwise.update(0xffffffff, check) ^ 0xffffffff, 0xcbf43926);
+        assert_eq!(bitwise.calls.load(Ordering::Relaxed), 1);
+        let split = SoftwareCrc.update(SoftwareCrc.update(0xffffffff, &check[..4]), &check[4..]);
+        assert_eq!(split, SoftwareCrc.update(0xffffffff, check));
+    }
+}
@@ -0,0 +1,293 @@
+//! Context of the most recent failed filesystem call.
+//!
+//! Filesystem calls return a bare [`FsError`] so that they remain cheap to compare and match on.
+//! The operation that failed, the path it was given, and whether the failure originated in the
+//! storage driver rather than in littlefs itself are recorded by the filesystem and available
+//! through [`LittleFs::last_error`] until the next call:
+//!
+//! ```ignore
+//! if let Err(err) = fs.mkdir("/data/logs") {
+//!     let context = fs.last_error().unwrap();
+//!     // "mkdir `/data/logs`: no such file or directory"
+//!     log::error!("{}", context);
+//! }
+//! ```
+//!
+//! The path is copied into the context, which takes up `NAME_MAX_LEN` bytes in every filesystem.
+//! It is kept with the default `error-path` feature; without it only the error, operation and
+//! storage error are recorded and [`ErrorContext::path`] returns `None`.
+//!
+//! [`FsError`]: ../enum.FsError.html
+//! [`LittleFs::last_error`]: ../struct.LittleFs.html#method.last_error
+
+use core::fmt;
+
+use crate::FsError;
+#[cfg(feature = "error-path")]
+use crate::NAME_MAX_LEN;
+
+/// Filesystem call that failed. More operations may be added as the filesystem gains calls.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum Operation {
+    Format,
+    Mount,
+    Unmount,
+    Remove,
+    Rename,
+    Stat,
+    FileOpen,
+    FileClose,
+    FileSync,
+    FileRead,
+    FileWrite,
+    FileSeek,
+    FileTruncate,
+    FileTell,
+    FileRewind,
+    FileSize,
+    Mkdir,
+    DirOpen,
+    DirClose,
+    DirRead,
+    DirSeek,
+    DirTell,
+    DirRewind,
+    CreateDirAll,
+    RemoveDirAll,
+    ReadInto,
+    Read,
+    ReadToString,
+    Write,
+    Append,
+    WriteAtomic,
+    Copy,
+    CopyDirAll,
+    RemoveStaleTemporaries,
+}
+
+impl fmt::Display for Operation {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match *self {
+            Operation::Format => "format",
+            Operation::Mount => "mount",
+            Operation::Unmount => "unmount",
+            Operation::Remove => "remove",
+            Operation::Rename => "rename",
+            Operation::Stat => "stat",
+            Operation::FileOpen => "file_open",
+            Operation::FileClose => "file_close",
+            Operation::FileSync => "file_sync",
+            Operation::FileRead => "file_read",
+            Operation::FileWrite => "file_write",
+            Operation::FileSeek => "file_seek",
+            Operation::FileTruncate => "file_truncate",
+            Operation::FileTell => "file_tell",
+            Operation::FileRewind => "file_rewind",
+            Operation::FileSize => "file_size",
+            Operation::Mkdir => "mkdir",
+            Operation::DirOpen => "dir_open",
+            Operation::DirClose => "dir_close",
+            Operation::DirRead => "dir_read",
+            Operation::DirSeek => "dir_seek",
+            Operation::DirTell => "dir_tell",
+            Operation::DirRewind => "dir_rewind",
+            Operation::CreateDirAll => "create_dir_all",
+            Operation::RemoveDirAll => "remove_dir_all",
+            Operation::ReadInto => "read_into",
+            Operation::Read => "read",
+            Operation::ReadToString => "read_to_string",
+            Operation::Write => "write",
+            Operation::Append => "append",
+            Operation::WriteAtomic => "write_atomic",
+            Operation::Copy => "copy",
+            Operation::CopyDirAll => "copy_dir_all",
+            Operation::RemoveStaleTemporaries => "remove_stale_temporaries",
+        };
+        f.write_str(name)
+    }
+}
+
+/// Failed filesystem call together with its operation, path and origin.
+#[derive(Clone, Copy)]
+pub struct ErrorContext {
+    error: FsError,
+    operation: Operation,
+    #[cfg(feature = "error-path")]
+    path: [u8; NAME_MAX_LEN],
+    #[cfg(feature = "error-path")]
+    path_len: Option<usize>,
+    storage_error: Option<FsError>,
+}
+
+impl ErrorContext {
+    pub(crate) fn new(
+        error: FsError,
+        operation: Operation,
+        path: Option<&str>,
+        storage_error: Option<FsError>,
+    ) -> Self {
+        let mut context = ErrorContext {
+            error,
+            operation,
+            #[cfg(feature = "error-path")]
+            path: [0; NAME_MAX_LEN],
+            #[cfg(feature = "error-path")]
+            path_len: None,
+            storage_error,
+        };
+        if let Some(path) = path {
+            context.set_path(path);
+        }
+        context
+    }
+
+    #[cfg(feature = "error-path")]
+    fn set_path(&mut self, path: &str) {
+        // Paths are truncated to the length littlefs accepts; keep the copy valid UTF-8.
+        let mut len = core::cmp::min(path.len(), NAME_MAX_LEN);
+        while !path.is_char_boundary(len) {
+            len -= 1;
+        }
+        self.path[..len].copy_from_slice(&path.as_bytes()[..len]);
+        self.path_len = Some(len);
+    }
+
+    #[cfg(not(feature = "error-path"))]
+    fn set_path(&mut self, _path: &str) {}
+
+    /// Error returned by the call.
+    pub fn error(&self) -> FsError {
+        self.error
+    }
+
+    /// Operation that failed.
+    pub fn operation(&self) -> Operation {
+        self.operation
+    }
+
+    /// Path given to the operation, if it takes one. The first path is kept for renames.
+    #[cfg(feature = "error-path")]
+    pub fn path(&self) -> Option<&str> {
+        let len = self.path_len?;
+        core::str::from_utf8(&self.path[..len]).ok()
+    }
+
+    /// Path given to the operation, which is not recorded without the `error-path` feature.
+    #[cfg(not(feature = "error-path"))]
+    pub fn path(&self) -> Option<&str> {
+        None
+    }
+
+    /// Error reported by the storage driver, if the failure originated in the storage rather
+    /// than in littlefs.
+    pub fn storage_error(&self) -> Option<FsError> {
+        self.storage_error
+    }
+
+    /// Whether the failure originated in the storage driver.
+    pub fn is_storage(&self) -> bool {
+        self.storage_error.is_some()
+    }
+}
+
+impl fmt::Debug for ErrorContext {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("ErrorContext")
+            .field("error", &self.error)
+            .field("operation", &self.operation)
+            .field("path", &self.path())
+            .field("storage_error", &self.storage_error)
+            .finish()
+    }
+}
+
+impl fmt::Display for ErrorContext {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.operation)?;
+        if let Some(path) = self.path() {
+            write!(f, " `{}`", path)?;
+        }
+        write!(f, ": {}", self.error)?;
+        if let Some(storage_error) = self.storage_error {
+            write!(f, " (storage: {})", storage_error)?;
+        }
+        Ok(())
+    }
+}
+
+impl From<ErrorContext> for FsError {
+    fn from(context: ErrorContext) -> Self {
+        context.error
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for ErrorContext {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        Some(&self.error)
+    }
+}
+
+#[cfg(feature = "std")]
+impl From<ErrorContext> for std::io::Error {
+    fn from(context: ErrorContext) -> Self {
+        std::io::Error::new(context.error.io_error_kind(), context)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    extern crate std;
+
+    use super::*;
+    use crate::tests::{FailingStorage, RamStorage};
+    use crate::LittleFs;
+    use std::string::ToString;
+
+    #[test]
+    fn test_error_display() {
+        assert_eq!(FsError::Noent.to_string(), "no such file or directory");
+        assert_eq!(FsError::Unknown(-1000).to_string(), "unknown error -1000");
+
+        let context = ErrorContext::new(FsError::Exist, Operation::Mkdir, Some("/etc"), None);
+        #[cfg(feature = "error-path")]
+        assert_eq!(context.to_string(), "mkdir `/etc`: file exists");
+        #[cfg(not(feature = "error-path"))]
+        assert_eq!(context.to_string(), "mkdir: file exists");
+        let context =
+            ErrorContext::new(FsError::Io, Operation::FileSync, None, Some(FsError::Nospc));
+        assert_eq!(
+            context.to_string(),
+            "file_sync: input/output error (storage: no space left on storage)"
+        );
+    }
+
+    #[test]
+    fn test_last_error() {
+        let mut lfs = LittleFs::new(RamStorage::default());
+        lfs.format().unwrap();
+        let mut lfs = lfs.mount().unwrap();
+        assert!(lfs.last_error().is_none());
+
+        assert_eq!(lfs.mkdir("/missing/dir"), Err(FsError::Noent));
+        let context = lfs.last_error().unwrap();
+        assert_eq!(context.error(), FsError::Noent);
+        assert_eq!(context.operation(), Operation::Mkdir);
+        #[cfg(feature = "error-path")]
+        assert_eq!(context.path(), Some("/missing/dir"));
+        assert!(!context.is_storage());
+
+        lfs.mkdir("/dir").unwrap();
+        assert!(lfs.last_error().is_none());
+        lfs.unmount().unwrap();
+
+        let mut lfs = LittleFs::new(FailingStorage);
+        assert_eq!(lfs.format(), Err(FsError::Io));
+        let context = lfs.last_error().unwrap();
+        assert_eq!(context.operation(), Operation::Format);
+        assert_eq!(context.path(), None);
+        assert_eq!(context.storage_error(), Some(FsError::Io));
+    }
+}
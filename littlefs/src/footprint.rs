@@ -0,0 +1,134 @@
+//! RAM footprint of a filesystem configuration.
+//!
+//! The buffers given by the const generic sizes are embedded in `LittleFs` and `File`, and the
+//! littlefs state in them and in `Dir`, so the RAM cost of a configuration follows from their
+//! sizes. [`Footprint`] collects them together with the parts they are made of, and is available
+//! at compile time through [`LittleFs::FOOTPRINT`]:
+//!
+//! ```ignore
+//! const RAM: usize = LittleFs::<Flash, 128, 128, 8>::FOOTPRINT.total(4, 2);
+//! ```
+//!
+//! With the `alloc` feature, a configuration set through `LittleFs::with_config` with buffers
+//! larger than the embedded ones has them allocated from the heap when mounting and opening
+//! files. Those allocations come on top of the footprint.
+//!
+//! [`LittleFs::FOOTPRINT`]: ../struct.LittleFs.html#associatedconstant.FOOTPRINT
+
+use core::mem::size_of;
+
+use crate::{Dir, File, LittleFs, Storage};
+use littlefs_sys as lfs;
+
+/// Memory required by a filesystem instance and each open file and directory, in bytes.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Footprint {
+    /// Size of the `LittleFs` instance, including the storage it owns.
+    pub filesystem: usize,
+    /// Part of `filesystem` taken by the storage.
+    pub storage: usize,
+    /// Part of `filesystem` taken by the littlefs state and configuration.
+    pub state: usize,
+    /// Part of `filesystem` taken by the read cache, program cache and lookahead bitmap.
+    pub buffers: usize,
+    /// Size of a `File`.
+    pub file: usize,
+    /// Part of `file` taken by the file cache.
+    pub file_cache: usize,
+    /// Size of a `Dir`.
+    pub dir: usize,
+}
+
+impl Footprint {
+    /// Footprint of the given filesystem type.
+    pub const fn of<
+        T: Storage,
+        const READ_SIZE: usize,
+        const PROG_SIZE: usize,
+        const LOOKAHEAD_SIZE: usize,
+    >() -> Self {
+        Footprint {
+            filesystem: size_of::<LittleFs<T, READ_SIZE, PROG_SIZE, LOOKAHEAD_SIZE>>(),
+            storage: size_of::<T>(),
+            state: size_of::<lfs::lfs_t>() + size_of::<lfs::lfs_config>(),
+            buffers: READ_SIZE + PROG_SIZE + LOOKAHEAD_SIZE,
+            file: size_of::<File<PROG_SIZE>>(),
+            file_cache: PROG_SIZE,
+            dir: size_of::<Dir>(),
+        }
+    }
+
+    /// Total memory used by the filesystem with the given number of files and directories open
+    /// at the same time.
+    pub const fn total(&self, files: usize, dirs: usize) -> usize {
+        self.filesystem + files * self.file + dirs * self.dir
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use crate::tests::RamStorage;
+    use crate::{BLOCK_COUNT, BLOCK_SIZE};
+
+    /// Size of `lfs_t` and `lfs_config` on 64-bit hosts, as laid out by the C compiler.
+    #[cfg(target_pointer_width = "64")]
+    const LFS_STATE: usize = 96 + 96;
+    /// Size of `lfs_file_t` and `lfs_file_config` on 64-bit hosts.
+    #[cfg(target_pointer_width = "64")]
+    const LFS_FILE: usize = 72 + 8;
+    /// Size of `lfs_dir_t` on 64-bit hosts.
+    #[cfg(target_pointer_width = "64")]
+    const LFS_DIR: usize = 48;
+
+    #[test]
+    fn test_default_footprint() {
+        let footprint = LittleFs::<RamStorage>::FOOTPRINT;
+        assert_eq!(footprint.storage, BLOCK_SIZE * BLOCK_COUNT);
+        assert_eq!(footprint.buffers, 256 + 256 + 16);
+        assert_eq!(footprint.file_cache, 256);
+        assert!(footprint.filesystem > footprint.storage + footprint.state + footprint.buffers);
+        assert_eq!(
+            footprint.total(3, 2) - footprint.total(1, 1),
+            2 * footprint.file + footprint.dir
+        );
+    }
+
+    #[cfg(target_pointer_width = "64")]
+    #[test]
+    fn test_footprint_abi() {
+        let footprint = LittleFs::<RamStorage>::FOOTPRINT;
+        assert_eq!(footprint.state, LFS_STATE);
+        assert_eq!(footprint.file, 256 + LFS_FILE);
+        assert_eq!(footprint.dir, LFS_DIR);
+        assert_eq!(
+            footprint.total(3, 2),
+            footprint.filesystem + 3 * (256 + LFS_FILE) + 2 * LFS_DIR
+        );
+
+        let sized = LittleFs::<RamStorage, 64, 128, 8>::FOOTPRINT;
+        assert_eq!(sized.state, LFS_STATE);
+        assert_eq!(sized.file, 128 + LFS_FILE);
+        assert_eq!(sized.dir, LFS_DIR);
+    }
+
+    #[test]
+    fn test_sized_footprint() {
+        type Fs = LittleFs<RamStorage, 64, 128, 8>;
+        const RAM: usize = Fs::FOOTPRINT.total(1, 1);
+        let default = LittleFs::<RamStorage>::FOOTPRINT;
+        assert_eq!(Fs::FOOTPRINT.buffers, 64 + 128 + 8);
+        assert_eq!(Fs::FOOTPRINT.file_cache, 128);
+        // Only the buffers differ between the configurations.
+        assert_eq!(
+            default.filesystem - Fs::FOOTPRINT.filesystem,
+            (256 - 64) + (256 - 128) + (16 - 8)
+        );
+        assert_eq!(default.file - Fs::FOOTPRINT.file, 256 - 128);
+        assert_eq!(
+            RAM,
+            Fs::FOOTPRINT.filesystem + Fs::FOOTPRINT.file + Fs::FOOTPRINT.dir
+        );
+    }
+}
@@ -0,0 +1,316 @@
+//! Key-value store on top of the filesystem.
+//!
+//! A [`Store`] keeps each value in a file named after its key inside one directory. Values are
+//! replaced with [`LittleFs::write_atomic`], so that after a power loss a key holds either its old
+//! or its new value.
+//!
+//! Keys are file names: they may not be empty, `.` or `..`, contain `/` or a null byte, or start
+//! with the prefix of atomic update temporaries, and the path of the value together with the
+//! temporary prefix must fit in the longest path littlefs accepts. Invalid keys are rejected with
+//! `FsError::Inval`.
+//!
+//! ```ignore
+//! let mut settings = Store::open(&mut fs, "/settings")?;
+//! settings.set("baud", b"115200")?;
+//! let mut buf = [0u8; 16];
+//! if let Some(len) = settings.get("baud", &mut buf)? {
+//!     // &buf[..len] == b"115200"
+//! }
+//! for entry in settings.iter() {
+//!     let entry = entry?;
+//!     // entry.name, entry.size
+//! }
+//! ```
+//!
+//! [`Store`]: struct.Store.html
+//! [`LittleFs::write_atomic`]: ../struct.LittleFs.html#method.write_atomic
+
+use crate::atomic::TEMP_PREFIX;
+use crate::path::{Path, PathBuf, SEPARATOR};
+use crate::{Dir, EntryType, FsError, Info, LittleFs, Storage, NAME_MAX_LEN};
+use crate::{DEFAULT_LOOKAHEAD_SIZE, DEFAULT_PROG_SIZE, DEFAULT_READ_SIZE};
+
+/// Values stored by key in a directory of a mounted filesystem.
+pub struct Store<
+    'a,
+    T: Storage,
+    const READ_SIZE: usize = DEFAULT_READ_SIZE,
+    const PROG_SIZE: usize = DEFAULT_PROG_SIZE,
+    const LOOKAHEAD_SIZE: usize = DEFAULT_LOOKAHEAD_SIZE,
+> {
+    fs: &'a mut LittleFs<T, READ_SIZE, PROG_SIZE, LOOKAHEAD_SIZE>,
+    dir: PathBuf,
+}
+
+impl<
+        'a,
+        T: Storage,
+        const READ_SIZE: usize,
+        const PROG_SIZE: usize,
+        const LOOKAHEAD_SIZE: usize,
+    > Store<'a, T, READ_SIZE, PROG_SIZE, LOOKAHEAD_SIZE>
+{
+    /// Open the store kept in directory `dir`, creating the directory and its parents if needed.
+    pub fn open<P: AsRef<Path>>(
+        fs: &'a mut LittleFs<T, READ_SIZE, PROG_SIZE, LOOKAHEAD_SIZE>,
+        dir: P,
+    ) -> Result<Self, FsError> {
+        let dir = dir.as_ref().normalize()?;
+        fs.create_dir_all(&dir)?;
+        Ok(Store { fs, dir })
+    }
+
+    /// Directory holding the values.
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    /// Read the value of `key` into `buf`, returning its length, or `None` if the key is not
+    /// set. Fails with `FBig` if the value is larger than `buf`.
+    pub fn get(&mut self, key: &str, buf: &mut [u8]) -> Result<Option<usize>, FsError> {
+        let path = self.path(key)?;
+        match self.fs.read_into(&path, buf) {
+            Ok(len) => Ok(Some(len)),
+            Err(FsError::Noent) => Ok(None),
+            Err(err) => Err(err),
+        }
+    }
+
+    /// Whether `key` is set.
+    pub fn contains(&mut self, key: &str) -> Result<bool, FsError> {
+        let path = self.path(key)?;
+        match self.fs.stat(&path, &mut Info::default()) {
+            Ok(()) => Ok(true),
+            Err(FsError::Noent) => Ok(false),
+            Err(err) => Err(err),
+        }
+    }
+
+    /// Set the value of `key`, replacing any previous value atomically.
+    pub fn set(&mut self, key: &str, value: &[u8]) -> Result<(), FsError> {
+        let path = self.path(key)?;
+        self.fs.write_atomic(&path, value)
+    }
+
+    /// Remove `key`, returning whether it was set.
+    pub fn remove(&mut self, key: &str) -> Result<bool, FsError> {
+        let path = self.path(key)?;
+        match self.fs.remove(&path) {
+            Ok(()) => Ok(true),
+            Err(FsError::Noent) => Ok(false),
+            Err(err) => Err(err),
+        }
+    }
+
+    /// Iterate over the keys of the store. Each entry holds the key as its name and the length
+    /// of the value as its size.
+    pub fn iter(&mut self) -> Iter<'_, 'a, T, READ_SIZE, PROG_SIZE, LOOKAHEAD_SIZE> {
+        Iter {
+            store: self,
+            dir: Dir::default(),
+            pos: None,
+            done: false,
+        }
+    }
+
+    /// Path of the value of `key`.
+    fn path(&self, key: &str) -> Result<PathBuf, FsError> {
+        if key.is_empty()
+            || key == "."
+            || key == ".."
+            || key.contains(SEPARATOR)
+            || key.contains('\0')
+            || key.starts_with(TEMP_PREFIX)
+        {
+            return Err(FsError::Inval);
+        }
+        let path: PathBuf = self.dir.join(key)?;
+        // Leave room for the prefix of the temporary used by atomic updates.
+        if path.as_str().len() + TEMP_PREFIX.len() > NAME_MAX_LEN {
+            return Err(FsError::Inval);
+        }
+        Ok(path)
+    }
+}
+
+/// Iterator over the keys of a [`Store`](struct.Store.html).
+///
+/// The directory is only open while an entry is read, so values may be read while iterating.
+pub struct Iter<
+    'b,
+    'a,
+    T: Storage,
+    const READ_SIZE: usize,
+    const PROG_SIZE: usize,
+    const LOOKAHEAD_SIZE: usize,
+> {
+    store: &'b mut Store<'a, T, READ_SIZE, PROG_SIZE, LOOKAHEAD_SIZE>,
+    dir: Dir,
+    pos: Option<usize>,
+    done: bool,
+}
+
+impl<
+        'b,
+        'a,
+        T: Storage,
+        const READ_SIZE: usize,
+        const PROG_SIZE: usize,
+        const LOOKAHEAD_SIZE: usize,
+    > Iter<'b, 'a, T, READ_SIZE, PROG_SIZE, LOOKAHEAD_SIZE>
+{
+    /// Read the value of `key` into `buf` while iterating, as `Store::get`.
+    pub fn get(&mut self, key: &str, buf: &mut [u8]) -> Result<Option<usize>, FsError> {
+        self.store.get(key, buf)
+    }
+
+    /// Read the next key following the current position.
+    fn read_next(&mut self) -> Result<Option<Info>, FsError> {
+        let fs = &mut *self.store.fs;
+        fs.dir_open(&mut self.dir, &self.store.dir)?;
+        let res = next_key(fs, &mut self.dir, &mut self.pos);
+        fs.close_dir_after(&mut self.dir, res)
+    }
+}
+
+/// Read the first key of the open directory `dir` after position `pos` and advance `pos` past
+/// it. Subdirectories, temporaries and names that are not valid UTF-8 are skipped.
+fn next_key<
+    T: Storage,
+    const READ_SIZE: usize,
+    const PROG_SIZE: usize,
+    const LOOKAHEAD_SIZE: usize,
+>(
+    fs: &mut LittleFs<T, READ_SIZE, PROG_SIZE, LOOKAHEAD_SIZE>,
+    dir: &mut Dir,
+    pos: &mut Option<usize>,
+) -> Result<Option<Info>, FsError> {
+    if let Some(pos) = *pos {
+        fs.dir_seek(dir, pos as isize)?;
+    }
+    while let Some(info) = fs.dir_read(dir)? {
+        let key = match info.name.as_str() {
+            Ok(key) => key,
+            Err(_) => continue,
+        };
+        if info.entry_type == EntryType::RegularFile && !key.starts_with(TEMP_PREFIX) {
+            *pos = Some(fs.dir_tell(dir)?);
+            return Ok(Some(info));
+        }
+    }
+    Ok(None)
+}
+
+impl<
+        'b,
+        'a,
+        T: Storage,
+        const READ_SIZE: usize,
+        const PROG_SIZE: usize,
+        const LOOKAHEAD_SIZE: usize,
+    > Iterator for Iter<'b, 'a, T, READ_SIZE, PROG_SIZE, LOOKAHEAD_SIZE>
+{
+    type Item = Result<Info, FsError>;
+
+    fn next(&mut self) -> Option<Result<Info, FsError>> {
+        if self.done {
+            return None;
+        }
+        let res = self.read_next();
+        self.done = !matches!(res, Ok(Some(_)));
+        res.transpose()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use crate::tests::RamStorage;
+
+    extern crate std;
+    use std::string::{String, ToString};
+    use std::vec::Vec;
+
+    fn keys<T: Storage>(store: &mut Store<T>) -> Vec<(String, usize)> {
+        let mut keys: Vec<_> = store
+            .iter()
+            .map(|entry| {
+                let entry = entry.unwrap();
+                (entry.name.to_string(), entry.size)
+            })
+            .collect();
+        keys.sort();
+        keys
+    }
+
+    #[test]
+    fn test_store() {
+        let mut lfs = LittleFs::new(RamStorage::default());
+        lfs.format().unwrap();
+        let mut lfs = lfs.mount().unwrap();
+        let mut buf = [0u8; 16];
+
+        let mut store = Store::open(&mut lfs, "/settings/./radio/").unwrap();
+        assert_eq!(store.dir(), "/settings/radio");
+        assert_eq!(store.get("baud", &mut buf), Ok(None));
+        assert!(!store.contains("baud").unwrap());
+        store.set("baud", b"9600").unwrap();
+        store.set("baud", b"115200").unwrap();
+        store.set("mode", b"auto").unwrap();
+        store.set("empty", b"").unwrap();
+        assert_eq!(store.get("baud", &mut buf), Ok(Some(6)));
+        assert_eq!(&buf[..6], b"115200");
+        assert_eq!(store.get("baud", &mut buf[..2]), Err(FsError::FBig));
+        assert_eq!(store.get("empty", &mut buf), Ok(Some(0)));
+        assert!(store.contains("mode").unwrap());
+
+        assert!(store.remove("empty").unwrap());
+        assert!(!store.remove("empty").unwrap());
+        assert_eq!(
+            keys(&mut store),
+            [("baud".to_string(), 6), ("mode".to_string(), 4)]
+        );
+
+        let mut iter = store.iter();
+        let first = iter.next().unwrap().unwrap();
+        let key = first.name.as_str().unwrap();
+        assert!(iter.get(key, &mut buf).unwrap().is_some());
+        assert!(iter.next().unwrap().is_ok());
+        assert!(iter.next().is_none());
+        assert!(iter.next().is_none());
+
+        let storage = lfs.unmount().unwrap();
+        let mut lfs = LittleFs::new(storage).mount().unwrap();
+        let mut store = Store::open(&mut lfs, "/settings/radio").unwrap();
+        assert_eq!(store.get("mode", &mut buf), Ok(Some(4)));
+        assert_eq!(&buf[..4], b"auto");
+        assert_eq!(keys(&mut store).len(), 2);
+        lfs.unmount().unwrap();
+    }
+
+    #[test]
+    fn test_store_keys() {
+        let mut lfs = LittleFs::new(RamStorage::default());
+        lfs.format().unwrap();
+        let mut lfs = lfs.mount().unwrap();
+        lfs.mkdir("/kv").unwrap();
+        lfs.mkdir("/kv/nested").unwrap();
+        lfs.write("/kv/.~stale", b"").unwrap();
+        let mut store = Store::open(&mut lfs, "/kv").unwrap();
+
+        for key in &["", ".", "..", "a/b", "a\0b", ".~tmp"] {
+            assert_eq!(store.set(key, b"x"), Err(FsError::Inval), "{:?}", key);
+            assert_eq!(store.get(key, &mut []), Err(FsError::Inval));
+        }
+        let longest = "k".repeat(NAME_MAX_LEN - "/kv/".len() - TEMP_PREFIX.len());
+        store.set(&longest, b"x").unwrap();
+        let too_long = std::format!("{}k", longest);
+        assert_eq!(store.set(&too_long, b"x"), Err(FsError::Inval));
+        store.set(".hidden", b"x").unwrap();
+
+        // Subdirectories and temporaries are not keys.
+        assert_eq!(keys(&mut store), [(".hidden".to_string(), 1), (longest, 1)]);
+        lfs.unmount().unwrap();
+    }
+}
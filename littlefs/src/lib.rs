@@ -12,22 +12,62 @@
 #![allow(unused_variables)]
 #![no_std]
 
+#[cfg(feature = "std")]
+extern crate std;
+
+#[cfg(feature = "alloc")]
+extern crate alloc;
+
 #[macro_use]
 extern crate bitflags;
 
-const READ_SIZE: usize = 256;
-const PROG_SIZE: usize = 256;
+/// Default size of the read cache in bytes.
+pub const DEFAULT_READ_SIZE: usize = 256;
+/// Default size of the program cache and of file caches in bytes.
+pub const DEFAULT_PROG_SIZE: usize = 256;
+/// Default size of the lookahead bitmap in bytes, tracking eight blocks per byte.
+pub const DEFAULT_LOOKAHEAD_SIZE: usize = 16;
+
 const BLOCK_SIZE: usize = 4096;
 const BLOCK_COUNT: usize = 32;
-const LOOKAHEAD: usize = 128;
 
+use core::marker::PhantomData;
+use core::mem::ManuallyDrop;
 use core::{cmp, fmt, mem, ptr, slice};
 use littlefs_sys as lfs;
 
 const NAME_MAX_LEN: usize = lfs::LFS_NAME_MAX as usize;
 
+pub mod atomic;
+pub mod contents;
+pub mod copy;
+#[cfg(feature = "crc")]
+pub mod crc;
+pub mod error;
+pub mod footprint;
+pub mod kv;
+#[cfg(any(feature = "log", feature = "defmt", feature = "alloc", feature = "crc"))]
+pub mod logging;
+pub mod mode;
+pub mod path;
+#[cfg(target_has_atomic = "8")]
+pub mod singleton;
+pub mod stats;
+pub mod tree;
+#[cfg(feature = "serde")]
+pub mod typed;
+pub mod wear;
+
+#[cfg(feature = "crc")]
+pub use crate::crc::{Crc, SoftwareCrc};
+pub use crate::error::{ErrorContext, Operation};
+pub use crate::footprint::Footprint;
+pub use crate::mode::{AppendFile, OpenFile, ReadFile, ReadWriteFile, WriteFile};
+pub use crate::path::{Path, PathBuf};
+pub use crate::stats::IoStats;
+
 /// Definition of errors that might be returned by filesystem functionality.
-#[derive(Debug)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum FsError {
     /// Input / output error occurred.
     Io,
@@ -57,6 +97,60 @@ pub enum FsError {
     Unknown(i32),
 }
 
+impl fmt::Display for FsError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let msg = match *self {
+            FsError::Io => "input/output error",
+            FsError::Corrupt => "corrupted filesystem",
+            FsError::Noent => "no such file or directory",
+            FsError::Exist => "file exists",
+            FsError::NotDir => "not a directory",
+            FsError::IsDir => "is a directory",
+            FsError::NotEmpty => "directory not empty",
+            FsError::Badf => "bad file descriptor",
+            FsError::FBig => "file too large",
+            FsError::Inval => "invalid argument",
+            FsError::Nospc => "no space left on storage",
+            FsError::Nomem => "out of memory",
+            FsError::Unknown(code) => return write!(f, "unknown error {}", code),
+        };
+        f.write_str(msg)
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for FsError {}
+
+#[cfg(feature = "std")]
+impl FsError {
+    /// Closest standard I/O error kind.
+    pub fn io_error_kind(&self) -> std::io::ErrorKind {
+        use std::io::ErrorKind;
+        match *self {
+            FsError::Io => ErrorKind::Other,
+            FsError::Corrupt => ErrorKind::InvalidData,
+            FsError::Noent => ErrorKind::NotFound,
+            FsError::Exist => ErrorKind::AlreadyExists,
+            FsError::NotDir => ErrorKind::NotADirectory,
+            FsError::IsDir => ErrorKind::IsADirectory,
+            FsError::NotEmpty => ErrorKind::DirectoryNotEmpty,
+            FsError::Badf => ErrorKind::InvalidInput,
+            FsError::FBig => ErrorKind::FileTooLarge,
+            FsError::Inval => ErrorKind::InvalidInput,
+            FsError::Nospc => ErrorKind::StorageFull,
+            FsError::Nomem => ErrorKind::OutOfMemory,
+            FsError::Unknown(_) => ErrorKind::Other,
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl From<FsError> for std::io::Error {
+    fn from(err: FsError) -> Self {
+        std::io::Error::new(err.io_error_kind(), err)
+    }
+}
+
 /// Definition of storage interface required by the filesystem.
 pub trait Storage {
     /// Read data from the storage device.
@@ -89,14 +183,27 @@ fn lfs_to_fserror(lfs_error: lfs::lfs_error) -> Result<(), FsError> {
 
 /// Convert an lfs error to a FsError while encoding a result of usize.
 /// This return code is common for file system operations like:
-/// read, write, seek.
+/// read, write, seek. Non-negative values are results; negative values are errors.
 fn lfs_to_usize_fserror(lfs_error: lfs::lfs_error) -> Result<usize, FsError> {
-    let err = lfs_to_fserror(lfs_error);
-    match err {
-        Ok(()) => Ok(0),
-        Err(FsError::Unknown(val)) => Ok(val as usize),
-        Err(val) => Err(val),
+    if lfs_error < 0 {
+        lfs_to_fserror(lfs_error)?;
+    }
+    Ok(lfs_error as usize)
+}
+
+/// Convert a block relative access requested by littlefs into an offset into storage. Accesses
+/// outside of the configured geometry, which a corrupted image can lead littlefs to request, are
+/// rejected.
+fn storage_offset(
+    cfg: &lfs::lfs_config,
+    block: lfs::lfs_block_t,
+    off: lfs::lfs_off_t,
+    size: lfs::lfs_size_t,
+) -> Option<usize> {
+    if block >= cfg.block_count || off.checked_add(size)? > cfg.block_size {
+        return None;
     }
+    Some(block as usize * cfg.block_size as usize + off as usize)
 }
 
 /// Definition of starting location for seeking within a file.
@@ -118,16 +225,43 @@ pub enum EntryType {
     Directory,
 }
 
-/// Wrapper around an array of u8 representing a filename in ASCII encoding.
+/// Reason for formatting the storage in `LittleFs::mount_or_format_with`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum FormatReason {
+    /// The superblock area is erased; the device has never been formatted.
+    Blank,
+    /// The superblock area holds data that is not a valid filesystem.
+    Damaged,
+}
+
+/// Result of `LittleFs::mount_or_format`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum MountOutcome {
+    /// An existing filesystem was mounted.
+    Mounted,
+    /// The storage was formatted for the given reason and the new filesystem mounted.
+    Formatted(FormatReason),
+}
+
+impl MountOutcome {
+    /// Whether a fresh filesystem was created.
+    pub fn formatted(&self) -> bool {
+        match *self {
+            MountOutcome::Mounted => false,
+            MountOutcome::Formatted(_) => true,
+        }
+    }
+}
+
+/// Wrapper around an array of u8 representing a filename. The name is stored null terminated
+/// and compared, ordered and hashed on its exact bytes.
+#[derive(Clone)]
 pub struct Filename([u8; NAME_MAX_LEN + 1]);
 
 impl fmt::Debug for Filename {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         write!(f, "Filename \"")?;
-        for b in self.0.iter() {
-            if *b == 0 {
-                break;
-            }
+        for b in self.as_bytes() {
             write!(f, "{}", *b as char)?;
         }
         write!(f, "\"")?;
@@ -135,6 +269,19 @@ impl fmt::Debug for Filename {
     }
 }
 
+impl fmt::Display for Filename {
+    /// Write the name, replacing bytes that are not valid UTF-8 by the replacement character.
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for chunk in self.as_bytes().utf8_chunks() {
+            f.write_str(chunk.valid())?;
+            if !chunk.invalid().is_empty() {
+                write!(f, "{}", char::REPLACEMENT_CHARACTER)?;
+            }
+        }
+        Ok(())
+    }
+}
+
 impl Default for Filename {
     fn default() -> Self {
         Filename([0; NAME_MAX_LEN + 1])
@@ -144,24 +291,84 @@ impl Default for Filename {
 impl Filename {
     /// Convert C character array to u8 filename.
     fn from_c_char_array(c_name: *const cty::c_char) -> Self {
-        let len = strlen(c_name);
+        let len = cmp::min(strlen(c_name), NAME_MAX_LEN);
         let name = unsafe { slice::from_raw_parts(c_name as *const u8, len) };
         let mut filename = Filename::default();
-        filename.0[..len].copy_from_slice(&name[..len]);
+        filename.0[..len].copy_from_slice(name);
         filename
     }
+
+    /// Bytes of the name, without the null terminator.
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.0[..self.len()]
+    }
+
+    /// The name as a string, if it is valid UTF-8.
+    pub fn as_str(&self) -> Result<&str, core::str::Utf8Error> {
+        core::str::from_utf8(self.as_bytes())
+    }
+
+    /// Length of the name in bytes.
+    pub fn len(&self) -> usize {
+        self.0.iter().position(|&b| b == 0).unwrap_or(NAME_MAX_LEN)
+    }
+
+    /// Whether the name is empty.
+    pub fn is_empty(&self) -> bool {
+        self.0[0] == 0
+    }
+}
+
+impl<'a> core::convert::TryFrom<&'a str> for Filename {
+    type Error = FsError;
+
+    /// Create a filename, failing with `Inval` if the name is longer than littlefs allows or
+    /// contains a null byte.
+    fn try_from(name: &'a str) -> Result<Self, Self::Error> {
+        if name.len() > NAME_MAX_LEN || name.as_bytes().contains(&0) {
+            return Err(FsError::Inval);
+        }
+        let mut filename = Filename::default();
+        filename.0[..name.len()].copy_from_slice(name.as_bytes());
+        Ok(filename)
+    }
+}
+
+impl PartialEq for Filename {
+    fn eq(&self, other: &Filename) -> bool {
+        self.as_bytes() == other.as_bytes()
+    }
+}
+
+impl Eq for Filename {}
+
+impl PartialOrd for Filename {
+    fn partial_cmp(&self, other: &Filename) -> Option<cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Filename {
+    fn cmp(&self, other: &Filename) -> cmp::Ordering {
+        self.as_bytes().cmp(other.as_bytes())
+    }
+}
+
+impl core::hash::Hash for Filename {
+    fn hash<H: core::hash::Hasher>(&self, state: &mut H) {
+        self.as_bytes().hash(state);
+    }
+}
+
+impl PartialEq<str> for Filename {
+    fn eq(&self, other: &str) -> bool {
+        self.as_bytes() == other.as_bytes()
+    }
 }
 
 impl PartialEq<&str> for Filename {
     fn eq(&self, other: &&str) -> bool {
-        let mut i = 0;
-        for s in other.chars() {
-            if self.0[i] != s as u8 {
-                return false;
-            }
-            i += 1;
-        }
-        return true;
+        self.as_bytes() == other.as_bytes()
     }
 }
 
@@ -177,6 +384,17 @@ pub struct Info {
     pub name: Filename,
 }
 
+impl Default for Info {
+    /// Empty info of a regular file, to be filled in by `stat` or `dir_read`.
+    fn default() -> Self {
+        Info {
+            entry_type: EntryType::RegularFile,
+            size: 0,
+            name: Filename::default(),
+        }
+    }
+}
+
 impl Info {
     /// Convert littlefs info struct into the one made available via the Rust interface.
     fn from_lfs_info(lfs_info: lfs::lfs_info) -> Self {
@@ -237,24 +455,107 @@ bitflags! {
     }
 }
 
+/// Cache and lookahead sizes and mount options used by the filesystem. The static buffers embedded
+/// in `LittleFs` and `File` hold the default sizes; with the `alloc` feature larger sizes are
+/// allocated from the Rust heap by the C library when the filesystem is mounted or a file is
+/// opened.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Config {
+    /// Minimum size of a read from storage; must divide the block size.
+    pub read_size: usize,
+    /// Minimum size of a program to storage; must divide the block size.
+    pub prog_size: usize,
+    /// Number of blocks tracked by the block allocator at a time; must be a multiple of 32.
+    pub lookahead: usize,
+    /// Remove the temporaries of interrupted atomic updates when mounting, by walking the whole
+    /// tree; see `LittleFs::remove_stale_temporaries`. Off by default.
+    pub remove_temporaries: bool,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Config {
+            read_size: DEFAULT_READ_SIZE,
+            prog_size: DEFAULT_PROG_SIZE,
+            lookahead: DEFAULT_LOOKAHEAD_SIZE * 8,
+            remove_temporaries: false,
+        }
+    }
+}
+
+impl Config {
+    /// Check the sizes against the requirements of littlefs and, without the `alloc` feature,
+    /// against the sizes of the static buffers. File caches are as large as the program cache.
+    fn validate(
+        &self,
+        read_buffer: usize,
+        prog_buffer: usize,
+        lookahead_buffer: usize,
+    ) -> Result<(), FsError> {
+        if self.read_size == 0
+            || BLOCK_SIZE % self.read_size != 0
+            || self.prog_size == 0
+            || BLOCK_SIZE % self.prog_size != 0
+            || self.lookahead == 0
+            || self.lookahead % 32 != 0
+        {
+            return Err(FsError::Inval);
+        }
+        if !cfg!(feature = "alloc")
+            && (self.read_size > read_buffer
+                || self.prog_size > prog_buffer
+                || self.lookahead / 8 > lookahead_buffer
+                || self.file_cache_size() > prog_buffer)
+        {
+            return Err(FsError::Nomem);
+        }
+        Ok(())
+    }
+
+    /// Size of the cache of a file, which is used for both reads and programs.
+    fn file_cache_size(&self) -> usize {
+        cmp::max(self.read_size, self.prog_size)
+    }
+}
+
+/// Pointer to the given static buffer if it can hold `size` bytes, otherwise null so that the C
+/// library allocates the buffer itself.
+fn buffer_or_null(buffer: &mut [u8], size: usize) -> *mut cty::c_void {
+    if size <= buffer.len() {
+        buffer.as_mut_ptr() as *mut cty::c_void
+    } else {
+        ptr::null_mut()
+    }
+}
+
 /// Definition of a file handle. File handles are used to interact with a file in the filesystem.
-pub struct File {
+/// LittleFS tracks open files by address so a handle must not be moved while it is open.
+///
+/// The size of the file cache must equal the program cache size of the filesystem the file is
+/// opened on.
+pub struct File<const CACHE_SIZE: usize = DEFAULT_PROG_SIZE> {
     /// Required scratch pad memory used by LittleFS implementation.
-    buffer: [u8; PROG_SIZE],
+    buffer: [u8; CACHE_SIZE],
+    /// Per file configuration, referenced by LittleFS for as long as the file is open.
+    config: lfs::lfs_file_config,
     /// Handle to the data used by LittleFS to track file operations.
     inner: lfs::lfs_file_t,
 }
 
-impl Default for File {
+impl<const CACHE_SIZE: usize> Default for File<CACHE_SIZE> {
     fn default() -> Self {
         File {
-            buffer: [0u8; PROG_SIZE],
+            buffer: [0u8; CACHE_SIZE],
+            config: lfs::lfs_file_config {
+                buffer: ptr::null_mut(),
+            },
             inner: unsafe { mem::uninitialized() },
         }
     }
 }
 
 /// Definition of a directory handle which is used to interact with a directory in the filesystem.
+/// LittleFS tracks open directories by address so a handle must not be moved while it is open.
 pub struct Dir {
     /// Handle to the data used by LittleFS to track directory operations.
     inner: lfs::lfs_dir_t,
@@ -268,137 +569,627 @@ impl Default for Dir {
     }
 }
 
+mod sealed {
+    pub trait Sealed {}
+}
+
+/// Whether a `LittleFs` is mounted, tracked in its type. Implemented by [`Mounted`] and
+/// [`Unmounted`] only.
+pub trait MountState: sealed::Sealed {
+    #[doc(hidden)]
+    const MOUNTED: bool;
+}
+
+/// State of a filesystem mounted on its storage. Filesystem, file and directory operations are
+/// available.
+pub enum Mounted {}
+
+/// State of a filesystem that is not mounted. The storage may be accessed directly.
+pub enum Unmounted {}
+
+impl sealed::Sealed for Mounted {}
+impl sealed::Sealed for Unmounted {}
+
+impl MountState for Mounted {
+    const MOUNTED: bool = true;
+}
+
+impl MountState for Unmounted {
+    const MOUNTED: bool = false;
+}
+
 /// An instance of the Little filesystem. This struct defines the method of interacting with the
 /// filesystem and contains all of the data required for the C software.
-pub struct LittleFs<T: Storage> {
+///
+/// The sizes in bytes of the read cache, the program cache and the lookahead bitmap are given by
+/// `READ_SIZE`, `PROG_SIZE` and `LOOKAHEAD_SIZE`. They are checked at compile time: the read and
+/// program sizes must divide the block size, the read size must not exceed the program size, and
+/// the lookahead must track a multiple of 32 blocks. Files opened on the filesystem must have a
+/// cache of `PROG_SIZE` bytes.
+///
+/// Whether the filesystem is mounted is part of its type. A filesystem is created `Unmounted`,
+/// where the storage may be accessed through `storage` and `storage_mut`; `mount` consumes it and
+/// returns the `Mounted` filesystem, which littlefs owns until it is unmounted again. The storage
+/// can thus never be modified behind the caches of littlefs. A mounted filesystem may be moved,
+/// but files and directories opened on it may not.
+pub struct LittleFs<
+    T: Storage,
+    const READ_SIZE: usize = DEFAULT_READ_SIZE,
+    const PROG_SIZE: usize = DEFAULT_PROG_SIZE,
+    const LOOKAHEAD_SIZE: usize = DEFAULT_LOOKAHEAD_SIZE,
+    S: MountState = Mounted,
+> {
     storage: T,
     lfs_config: lfs::lfs_config,
     lfs: lfs::lfs_t,
+    config: Config,
     read_buffer: [u8; READ_SIZE],
     prog_buffer: [u8; PROG_SIZE],
-    lookahead_buffer: [u8; LOOKAHEAD / 8],
+    lookahead_buffer: [u8; LOOKAHEAD_SIZE],
+    io_stats: Option<IoStats>,
+    last_io_stats: IoStats,
+    storage_error: Option<FsError>,
+    last_error: Option<ErrorContext>,
+    state: PhantomData<S>,
+}
+
+/// Failure to mount a filesystem, handing back the unmounted filesystem so that the storage can
+/// be inspected, formatted or recovered.
+pub struct MountError<
+    T: Storage,
+    const READ_SIZE: usize = DEFAULT_READ_SIZE,
+    const PROG_SIZE: usize = DEFAULT_PROG_SIZE,
+    const LOOKAHEAD_SIZE: usize = DEFAULT_LOOKAHEAD_SIZE,
+> {
+    /// Reason the filesystem could not be mounted.
+    pub error: FsError,
+    /// The filesystem that failed to mount.
+    pub fs: LittleFs<T, READ_SIZE, PROG_SIZE, LOOKAHEAD_SIZE, Unmounted>,
 }
 
-/// Interface to the LittleFS.
-impl<T: Storage> LittleFs<T> {
+impl<T: Storage, const READ_SIZE: usize, const PROG_SIZE: usize, const LOOKAHEAD_SIZE: usize>
+    fmt::Debug for MountError<T, READ_SIZE, PROG_SIZE, LOOKAHEAD_SIZE>
+{
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("MountError")
+            .field("error", &self.error)
+            .finish()
+    }
+}
+
+impl<T: Storage, const READ_SIZE: usize, const PROG_SIZE: usize, const LOOKAHEAD_SIZE: usize>
+    From<MountError<T, READ_SIZE, PROG_SIZE, LOOKAHEAD_SIZE>> for FsError
+{
+    fn from(err: MountError<T, READ_SIZE, PROG_SIZE, LOOKAHEAD_SIZE>) -> Self {
+        err.error
+    }
+}
+
+/// Failure to unmount a filesystem, handing back the filesystem so that its storage is not lost.
+/// littlefs has released its state either way, so the filesystem is unmounted.
+pub struct UnmountError<
+    T: Storage,
+    const READ_SIZE: usize = DEFAULT_READ_SIZE,
+    const PROG_SIZE: usize = DEFAULT_PROG_SIZE,
+    const LOOKAHEAD_SIZE: usize = DEFAULT_LOOKAHEAD_SIZE,
+> {
+    /// Reason the filesystem could not be unmounted cleanly.
+    pub error: FsError,
+    /// The filesystem that failed to unmount.
+    pub fs: LittleFs<T, READ_SIZE, PROG_SIZE, LOOKAHEAD_SIZE, Unmounted>,
+}
+
+impl<T: Storage, const READ_SIZE: usize, const PROG_SIZE: usize, const LOOKAHEAD_SIZE: usize>
+    fmt::Debug for UnmountError<T, READ_SIZE, PROG_SIZE, LOOKAHEAD_SIZE>
+{
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("UnmountError")
+            .field("error", &self.error)
+            .finish()
+    }
+}
+
+impl<T: Storage, const READ_SIZE: usize, const PROG_SIZE: usize, const LOOKAHEAD_SIZE: usize>
+    From<UnmountError<T, READ_SIZE, PROG_SIZE, LOOKAHEAD_SIZE>> for FsError
+{
+    fn from(err: UnmountError<T, READ_SIZE, PROG_SIZE, LOOKAHEAD_SIZE>) -> Self {
+        err.error
+    }
+}
+
+/// Constructors of the LittleFS with the default buffer sizes.
+impl<T: Storage>
+    LittleFs<T, DEFAULT_READ_SIZE, DEFAULT_PROG_SIZE, DEFAULT_LOOKAHEAD_SIZE, Unmounted>
+{
     /// Create a new instance of the LittleFS.
     pub fn new(storage: T) -> Self {
+        LittleFs::new_sized(storage)
+    }
+
+    /// Create a new instance of the LittleFS using the given cache and lookahead sizes. The sizes
+    /// are checked when the filesystem is formatted or mounted.
+    pub fn with_config(storage: T, config: Config) -> Self {
+        LittleFs::with_config_sized(storage, config)
+    }
+}
+
+impl<
+        T: Storage,
+        const READ_SIZE: usize,
+        const PROG_SIZE: usize,
+        const LOOKAHEAD_SIZE: usize,
+        S: MountState,
+    > Drop for LittleFs<T, READ_SIZE, PROG_SIZE, LOOKAHEAD_SIZE, S>
+{
+    /// Unmount the filesystem if it is mounted.
+    fn drop(&mut self) {
+        if S::MOUNTED {
+            let _ = self.release();
+        }
+    }
+}
+
+/// Interface to the LittleFS common to mounted and unmounted filesystems.
+impl<
+        T: Storage,
+        const READ_SIZE: usize,
+        const PROG_SIZE: usize,
+        const LOOKAHEAD_SIZE: usize,
+        S: MountState,
+    > LittleFs<T, READ_SIZE, PROG_SIZE, LOOKAHEAD_SIZE, S>
+{
+    /// Compile time checks of the buffer sizes, evaluated when a filesystem is created.
+    const SIZE_CHECKS: () = {
+        assert!(
+            READ_SIZE > 0 && BLOCK_SIZE % READ_SIZE == 0,
+            "read size must divide the block size"
+        );
+        assert!(
+            PROG_SIZE > 0 && BLOCK_SIZE % PROG_SIZE == 0,
+            "program size must divide the block size"
+        );
+        assert!(
+            READ_SIZE <= PROG_SIZE,
+            "read size must not exceed the program size used for file caches"
+        );
+        assert!(
+            LOOKAHEAD_SIZE > 0 && LOOKAHEAD_SIZE % 4 == 0,
+            "lookahead must track a multiple of 32 blocks"
+        );
+    };
+
+    /// RAM used by filesystems of this type and their files and directories.
+    pub const FOOTPRINT: Footprint = Footprint::of::<T, READ_SIZE, PROG_SIZE, LOOKAHEAD_SIZE>();
+
+    /// Cache and lookahead sizes used by the filesystem.
+    pub fn config(&self) -> &Config {
+        &self.config
+    }
+
+    /// Start recording the storage operations performed by each filesystem call. Counting
+    /// starts from zero.
+    pub fn enable_io_stats(&mut self) {
+        self.io_stats = Some(IoStats::default());
+        self.last_io_stats = IoStats::default();
+    }
+
+    /// Stop recording storage operations.
+    pub fn disable_io_stats(&mut self) {
+        self.io_stats = None;
+    }
+
+    /// Reset the recorded storage operations back to zero.
+    pub fn reset_io_stats(&mut self) {
+        if self.io_stats.is_some() {
+            self.enable_io_stats();
+        }
+    }
+
+    /// Storage operations recorded since I/O accounting was enabled or last reset.
+    pub fn io_stats(&self) -> Option<IoStats> {
+        self.io_stats
+    }
+
+    /// Storage operations performed by the most recent filesystem call.
+    pub fn last_io_stats(&self) -> Option<IoStats> {
+        self.io_stats.map(|_| self.last_io_stats)
+    }
+
+    /// Context of the most recent filesystem call if it failed.
+    pub fn last_error(&self) -> Option<&ErrorContext> {
+        self.last_error.as_ref()
+    }
+
+    /// Return the storage used by the filesystem, unmounting it first if it is still mounted.
+    pub fn into_inner(self) -> T {
+        let mut this = ManuallyDrop::new(self);
+        if S::MOUNTED {
+            let _ = this.release();
+        }
+        // The filesystem is no longer mounted so nothing is left for Drop to do; the storage is
+        // moved out and the remaining fields need no dropping.
+        unsafe { ptr::read(&this.storage) }
+    }
+
+    /// Move the filesystem into another mount state without unmounting or mounting it.
+    fn into_state<N: MountState>(self) -> LittleFs<T, READ_SIZE, PROG_SIZE, LOOKAHEAD_SIZE, N> {
+        let this = ManuallyDrop::new(self);
+        // Every field but the storage is plain data, so copying them and moving the storage out
+        // transfers the filesystem without running Drop on the old value.
+        LittleFs {
+            storage: unsafe { ptr::read(&this.storage) },
+            lfs_config: this.lfs_config,
+            lfs: this.lfs,
+            config: this.config,
+            read_buffer: this.read_buffer,
+            prog_buffer: this.prog_buffer,
+            lookahead_buffer: this.lookahead_buffer,
+            io_stats: this.io_stats,
+            last_io_stats: this.last_io_stats,
+            storage_error: this.storage_error,
+            last_error: this.last_error,
+            state: PhantomData,
+        }
+    }
+
+    /// Unmount the filesystem, which must be mounted.
+    fn release(&mut self) -> Result<(), FsError> {
+        self.begin_op();
+        let res = unsafe { lfs::lfs_unmount(&mut self.lfs) };
+        self.finish(Operation::Unmount, None, lfs_to_fserror(res))
+    }
+
+    /// Point the state of a mounted filesystem at the current location of this struct, which
+    /// may have moved since it was mounted. Buffers allocated by the C library stay in place.
+    fn rebind(&mut self) {
+        self.lfs_config.context = self as *mut _ as *mut cty::c_void;
+        self.lfs_config.read = Some(Self::lfs_config_read);
+        self.lfs_config.prog = Some(Self::lfs_config_prog);
+        self.lfs_config.erase = Some(Self::lfs_config_erase);
+        self.lfs_config.sync = Some(Self::lfs_config_sync);
+        if !self.lfs_config.read_buffer.is_null() {
+            self.lfs_config.read_buffer = self.read_buffer.as_mut_ptr() as *mut cty::c_void;
+            self.lfs.rcache.buffer = self.read_buffer.as_mut_ptr();
+        }
+        if !self.lfs_config.prog_buffer.is_null() {
+            self.lfs_config.prog_buffer = self.prog_buffer.as_mut_ptr() as *mut cty::c_void;
+            self.lfs.pcache.buffer = self.prog_buffer.as_mut_ptr();
+        }
+        if !self.lfs_config.lookahead_buffer.is_null() {
+            self.lfs_config.lookahead_buffer =
+                self.lookahead_buffer.as_mut_ptr() as *mut cty::c_void;
+            self.lfs.free.buffer = self.lookahead_buffer.as_mut_ptr() as *mut u32;
+        }
+        self.lfs.cfg = &self.lfs_config;
+    }
+}
+
+/// Interface to an unmounted LittleFS.
+impl<T: Storage, const READ_SIZE: usize, const PROG_SIZE: usize, const LOOKAHEAD_SIZE: usize>
+    LittleFs<T, READ_SIZE, PROG_SIZE, LOOKAHEAD_SIZE, Unmounted>
+{
+    /// Create a new instance of the LittleFS with the buffer sizes of its type.
+    ///
+    /// Sizes violating the requirements of littlefs are rejected at compile time, here a
+    /// lookahead that does not track a multiple of 32 blocks:
+    ///
+    /// ```compile_fail
+    /// # use littlefs::{FsError, LittleFs, Storage};
+    /// # struct Flash;
+    /// # impl Storage for Flash {
+    /// #     fn read(&self, _: usize, buf: &mut [u8]) -> Result<usize, FsError> { Ok(buf.len()) }
+    /// #     fn write(&mut self, _: usize, data: &[u8]) -> Result<usize, FsError> { Ok(data.len()) }
+    /// #     fn erase(&mut self, _: usize, len: usize) -> Result<usize, FsError> { Ok(len) }
+    /// # }
+    /// let fs: LittleFs<Flash, 128, 128, 6, _> = LittleFs::new_sized(Flash);
+    /// ```
+    pub fn new_sized(storage: T) -> Self {
+        let config = Config {
+            read_size: READ_SIZE,
+            prog_size: PROG_SIZE,
+            lookahead: LOOKAHEAD_SIZE * 8,
+            remove_temporaries: false,
+        };
+        LittleFs::with_config_sized(storage, config)
+    }
+
+    /// Create a new instance of the LittleFS with the buffer sizes of its type, using the given
+    /// cache and lookahead sizes. The sizes are checked when the filesystem is formatted or
+    /// mounted.
+    pub fn with_config_sized(storage: T, config: Config) -> Self {
+        #[allow(clippy::let_unit_value)]
+        let () = Self::SIZE_CHECKS;
         LittleFs {
             storage: storage,
             lfs: unsafe { mem::uninitialized::<lfs::lfs>() },
             lfs_config: unsafe { mem::uninitialized::<lfs::lfs_config>() },
+            config: config,
             read_buffer: [0u8; READ_SIZE],
             prog_buffer: [0u8; PROG_SIZE],
-            lookahead_buffer: [0u8; LOOKAHEAD / 8],
+            lookahead_buffer: [0u8; LOOKAHEAD_SIZE],
+            io_stats: None,
+            last_io_stats: IoStats::default(),
+            storage_error: None,
+            last_error: None,
+            state: PhantomData,
         }
     }
 
+    /// Borrow the storage of the filesystem.
+    pub fn storage(&self) -> &T {
+        &self.storage
+    }
+
+    /// Mutably borrow the storage of the filesystem, for example to inspect or erase it before
+    /// it is mounted.
+    pub fn storage_mut(&mut self) -> &mut T {
+        &mut self.storage
+    }
+
     /// Format the filesystem.
     pub fn format(&mut self) -> Result<(), FsError> {
+        self.begin_op();
+        #[cfg(any(feature = "log", feature = "defmt", feature = "alloc", feature = "crc"))]
+        logging::install();
+        let res = self.config.validate(READ_SIZE, PROG_SIZE, LOOKAHEAD_SIZE);
+        self.finish(Operation::Format, None, res)?;
         self.lfs_config = self.create_lfs_config();
         let res = unsafe { lfs::lfs_format(&mut self.lfs, &self.lfs_config) };
-        lfs_to_fserror(res)
+        self.finish(Operation::Format, None, lfs_to_fserror(res))
     }
 
-    /// Mount the filesystem.
-    pub fn mount(&mut self) -> Result<(), FsError> {
+    /// Mount the filesystem. On failure the unmounted filesystem is handed back with the error.
+    ///
+    /// Stale temporaries of atomic updates are then removed if `Config::remove_temporaries` is
+    /// set. The mount succeeds even if that fails, with the failure left in `last_error`.
+    #[allow(clippy::result_large_err)]
+    pub fn mount(
+        mut self,
+    ) -> Result<
+        LittleFs<T, READ_SIZE, PROG_SIZE, LOOKAHEAD_SIZE>,
+        MountError<T, READ_SIZE, PROG_SIZE, LOOKAHEAD_SIZE>,
+    > {
+        match self.mount_in_place() {
+            Ok(()) => {
+                let mut fs = self.into_state();
+                if fs.config.remove_temporaries {
+                    // Left in `last_error`; the temporaries are removed on a later mount otherwise.
+                    let _ = fs.remove_stale_temporaries();
+                }
+                Ok(fs)
+            }
+            Err(error) => Err(MountError { error, fs: self }),
+        }
+    }
+
+    /// Mount the filesystem, formatting the storage first if it does not hold a valid
+    /// filesystem.
+    #[allow(clippy::result_large_err)]
+    pub fn mount_or_format(
+        self,
+    ) -> Result<
+        (
+            LittleFs<T, READ_SIZE, PROG_SIZE, LOOKAHEAD_SIZE>,
+            MountOutcome,
+        ),
+        MountError<T, READ_SIZE, PROG_SIZE, LOOKAHEAD_SIZE>,
+    > {
+        self.mount_or_format_with(|_| true)
+    }
+
+    /// Mount the filesystem. If the storage does not hold a valid filesystem, `allow_format` is
+    /// asked whether to format it; when it declines, the mount error is returned and the storage
+    /// is left untouched. Errors other than a corrupt filesystem are returned without formatting.
+    #[allow(clippy::result_large_err)]
+    pub fn mount_or_format_with<F>(
+        mut self,
+        allow_format: F,
+    ) -> Result<
+        (
+            LittleFs<T, READ_SIZE, PROG_SIZE, LOOKAHEAD_SIZE>,
+            MountOutcome,
+        ),
+        MountError<T, READ_SIZE, PROG_SIZE, LOOKAHEAD_SIZE>,
+    >
+    where
+        F: FnOnce(FormatReason) -> bool,
+    {
+        match self.mount_or_format_in_place(allow_format) {
+            Ok(outcome) => {
+                let mut fs = self.into_state();
+                if fs.config.remove_temporaries {
+                    // Left in `last_error`; the temporaries are removed on a later mount otherwise.
+                    let _ = fs.remove_stale_temporaries();
+                }
+                Ok((fs, outcome))
+            }
+            Err(error) => Err(MountError { error, fs: self }),
+        }
+    }
+
+    /// Mount the filesystem without changing its type; it must be converted to `Mounted` on
+    /// success.
+    fn mount_in_place(&mut self) -> Result<(), FsError> {
+        self.begin_op();
+        #[cfg(any(feature = "log", feature = "defmt", feature = "alloc", feature = "crc"))]
+        logging::install();
+        let res = self.config.validate(READ_SIZE, PROG_SIZE, LOOKAHEAD_SIZE);
+        self.finish(Operation::Mount, None, res)?;
         self.lfs_config = self.create_lfs_config();
         let res = unsafe { lfs::lfs_mount(&mut self.lfs, &self.lfs_config) };
-        lfs_to_fserror(res)
+        self.finish(Operation::Mount, None, lfs_to_fserror(res))
     }
 
-    /// Unmount the filesystem.
-    pub fn unmount(mut self) -> Result<(), FsError> {
-        let res = unsafe { lfs::lfs_unmount(&mut self.lfs) };
-        lfs_to_fserror(res)
+    /// Mount the filesystem without changing its type, formatting it as allowed by
+    /// `allow_format`; it must be converted to `Mounted` on success.
+    fn mount_or_format_in_place<F>(&mut self, allow_format: F) -> Result<MountOutcome, FsError>
+    where
+        F: FnOnce(FormatReason) -> bool,
+    {
+        match self.mount_in_place() {
+            Ok(()) => return Ok(MountOutcome::Mounted),
+            Err(FsError::Corrupt) => (),
+            Err(err) => return Err(err),
+        }
+
+        let reason = if self.superblocks_erased()? {
+            FormatReason::Blank
+        } else {
+            FormatReason::Damaged
+        };
+        if !allow_format(reason) {
+            return Err(FsError::Corrupt);
+        }
+        self.format()?;
+        self.mount_in_place()?;
+        Ok(MountOutcome::Formatted(reason))
+    }
+
+    /// Whether both superblock blocks hold nothing but a single repeated byte value, as left by
+    /// an erase.
+    fn superblocks_erased(&self) -> Result<bool, FsError> {
+        let mut buf = [0u8; 64];
+        for block in 0..2 {
+            let mut erased = None;
+            for off in (0..BLOCK_SIZE).step_by(buf.len()) {
+                self.storage.read(block * BLOCK_SIZE + off, &mut buf)?;
+                let value = *erased.get_or_insert(buf[0]);
+                if buf.iter().any(|&b| b != value) {
+                    return Ok(false);
+                }
+            }
+        }
+        Ok(true)
+    }
+}
+
+/// Interface to a mounted LittleFS.
+impl<T: Storage, const READ_SIZE: usize, const PROG_SIZE: usize, const LOOKAHEAD_SIZE: usize>
+    LittleFs<T, READ_SIZE, PROG_SIZE, LOOKAHEAD_SIZE, Mounted>
+{
+    /// Unmount the filesystem and return the storage it was using. The filesystem is handed
+    /// back in the error if unmounting fails.
+    pub fn unmount(self) -> Result<T, UnmountError<T, READ_SIZE, PROG_SIZE, LOOKAHEAD_SIZE>> {
+        self.into_unmounted().map(LittleFs::into_inner)
+    }
+
+    /// Unmount the filesystem, keeping it around to access its storage or mount it again. The
+    /// filesystem is handed back in the error if unmounting fails.
+    pub fn into_unmounted(
+        mut self,
+    ) -> Result<
+        LittleFs<T, READ_SIZE, PROG_SIZE, LOOKAHEAD_SIZE, Unmounted>,
+        UnmountError<T, READ_SIZE, PROG_SIZE, LOOKAHEAD_SIZE>,
+    > {
+        let res = self.release();
+        let fs = self.into_state();
+        match res {
+            Ok(()) => Ok(fs),
+            Err(error) => Err(UnmountError { error, fs }),
+        }
     }
 
     /// Remove a file or directory.
-    pub fn remove(&mut self, path: &str) -> Result<(), FsError> {
-        let mut cstr = [0u8; NAME_MAX_LEN + 1];
-        let len = cmp::min(NAME_MAX_LEN, path.len());
-        cstr[..len].copy_from_slice(&path.as_bytes()[..len]);
-        let res =
-            unsafe { lfs::lfs_remove(&mut self.lfs, &cstr as *const _ as *const cty::c_char) };
-        lfs_to_fserror(res)
+    pub fn remove<P: AsRef<Path>>(&mut self, path: P) -> Result<(), FsError> {
+        let path = path.as_ref();
+        self.begin_op();
+        let res = path.to_c_path().and_then(|cstr| {
+            let res =
+                unsafe { lfs::lfs_remove(&mut self.lfs, cstr.as_ptr() as *const cty::c_char) };
+            lfs_to_fserror(res)
+        });
+        self.finish(Operation::Remove, Some(path.as_str()), res)
     }
 
     /// Rename or move a file or directory.
-    pub fn rename(&mut self, old_path: &str, new_path: &str) -> Result<(), FsError> {
-        let mut oldpath = [0u8; NAME_MAX_LEN + 1];
-        let oldpathlen = cmp::min(NAME_MAX_LEN, old_path.len());
-        let mut newpath = [0u8; NAME_MAX_LEN + 1];
-        let newpathlen = cmp::min(NAME_MAX_LEN, new_path.len());
-        oldpath[..oldpathlen].copy_from_slice(&old_path.as_bytes()[..oldpathlen]);
-        newpath[..newpathlen].copy_from_slice(&new_path.as_bytes()[..newpathlen]);
-        let res = unsafe {
-            lfs::lfs_rename(
-                &mut self.lfs,
-                oldpath.as_ptr() as *const cty::c_char,
-                newpath.as_ptr() as *const cty::c_char,
-            )
-        };
-        lfs_to_fserror(res)
+    pub fn rename<P: AsRef<Path>, Q: AsRef<Path>>(
+        &mut self,
+        old_path: P,
+        new_path: Q,
+    ) -> Result<(), FsError> {
+        let (old_path, new_path) = (old_path.as_ref(), new_path.as_ref());
+        self.begin_op();
+        let res = old_path.to_c_path().and_then(|oldpath| {
+            let newpath = new_path.to_c_path()?;
+            let res = unsafe {
+                lfs::lfs_rename(
+                    &mut self.lfs,
+                    oldpath.as_ptr() as *const cty::c_char,
+                    newpath.as_ptr() as *const cty::c_char,
+                )
+            };
+            lfs_to_fserror(res)
+        });
+        self.finish(Operation::Rename, Some(old_path.as_str()), res)
     }
 
     /// Populate info for file or directory at specified path.
-    pub fn stat(&mut self, path: &str, info: &mut Info) -> Result<(), FsError> {
-        let mut cstr = [0u8; NAME_MAX_LEN + 1];
-        let len = cmp::min(NAME_MAX_LEN, path.len());
-        cstr[..len].copy_from_slice(&path.as_bytes()[..len]);
-
+    pub fn stat<P: AsRef<Path>>(&mut self, path: P, info: &mut Info) -> Result<(), FsError> {
+        let path = path.as_ref();
+        self.begin_op();
         let mut lfs_info: lfs::lfs_info = unsafe { mem::uninitialized() };
-        let res = unsafe {
-            lfs::lfs_stat(
-                &mut self.lfs,
-                cstr.as_ptr() as *const cty::c_char,
-                &mut lfs_info,
-            )
-        };
-
+        let res = path.to_c_path().and_then(|cstr| {
+            let res = unsafe {
+                lfs::lfs_stat(
+                    &mut self.lfs,
+                    cstr.as_ptr() as *const cty::c_char,
+                    &mut lfs_info,
+                )
+            };
+            lfs_to_fserror(res)
+        });
+
+        self.finish(Operation::Stat, Some(path.as_str()), res)?;
         *info = Info::from_lfs_info(lfs_info);
-        lfs_to_fserror(res)
+        Ok(())
     }
 
     /// Open a file at the given path.
-    pub fn file_open(
+    pub fn file_open<P: AsRef<Path>>(
         &mut self,
-        file: &mut File,
-        path: &str,
+        file: &mut File<PROG_SIZE>,
+        path: P,
         flags: FileOpenFlags,
     ) -> Result<(), FsError> {
-        let mut cstr_path = [0u8; NAME_MAX_LEN];
-        let len = cmp::min(NAME_MAX_LEN - 1, path.len());
-        cstr_path[..len].copy_from_slice(&path.as_bytes()[..len]);
-        let file_cfg = lfs::lfs_file_config {
-            buffer: file.buffer.as_mut_ptr() as *mut cty::c_void,
-        };
-        let res = unsafe {
-            lfs::lfs_file_opencfg(
-                &mut self.lfs,
-                &mut file.inner,
-                cstr_path.as_ptr() as *const cty::c_char,
-                flags.bits() as i32,
-                &file_cfg,
-            )
-        };
-        lfs_to_fserror(res)
+        let path = path.as_ref();
+        self.begin_op();
+        file.config.buffer = buffer_or_null(&mut file.buffer, self.config.file_cache_size());
+        let res = path.to_c_path().and_then(|cstr_path| {
+            let res = unsafe {
+                lfs::lfs_file_opencfg(
+                    &mut self.lfs,
+                    &mut file.inner,
+                    cstr_path.as_ptr() as *const cty::c_char,
+                    flags.bits() as i32,
+                    &file.config,
+                )
+            };
+            lfs_to_fserror(res)
+        });
+        self.finish(Operation::FileOpen, Some(path.as_str()), res)
     }
 
     /// Close out the given file.
-    pub fn file_close(&mut self, mut file: File) -> Result<(), FsError> {
+    pub fn file_close(&mut self, file: &mut File<PROG_SIZE>) -> Result<(), FsError> {
+        self.begin_op();
         let res = unsafe { lfs::lfs_file_close(&mut self.lfs, &mut file.inner) };
-        lfs_to_fserror(res)
+        self.finish(Operation::FileClose, None, lfs_to_fserror(res))
     }
 
     /// Synchronize file contents to storage.
-    pub fn file_sync(&mut self, mut file: File) -> Result<(), FsError> {
+    pub fn file_sync(&mut self, file: &mut File<PROG_SIZE>) -> Result<(), FsError> {
+        self.begin_op();
         let res = unsafe { lfs::lfs_file_sync(&mut self.lfs, &mut file.inner) };
-        lfs_to_fserror(res)
+        self.finish(Operation::FileSync, None, lfs_to_fserror(res))
     }
 
     /// Read data from file.
-    pub fn file_read(&mut self, file: &mut File, buf: &mut [u8]) -> Result<usize, FsError> {
+    pub fn file_read(
+        &mut self,
+        file: &mut File<PROG_SIZE>,
+        buf: &mut [u8],
+    ) -> Result<usize, FsError> {
+        self.begin_op();
         let res = unsafe {
             lfs::lfs_file_read(
                 &mut self.lfs,
@@ -407,11 +1198,12 @@ impl<T: Storage> LittleFs<T> {
                 buf.len() as u32,
             )
         };
-        lfs_to_usize_fserror(res)
+        self.finish(Operation::FileRead, None, lfs_to_usize_fserror(res))
     }
 
     /// Write data to file.
-    pub fn file_write(&mut self, file: &mut File, buf: &[u8]) -> Result<usize, FsError> {
+    pub fn file_write(&mut self, file: &mut File<PROG_SIZE>, buf: &[u8]) -> Result<usize, FsError> {
+        self.begin_op();
         let res = unsafe {
             lfs::lfs_file_write(
                 &mut self.lfs,
@@ -420,130 +1212,227 @@ impl<T: Storage> LittleFs<T> {
                 buf.len() as u32,
             )
         };
-        lfs_to_usize_fserror(res)
+        let res = self.finish(Operation::FileWrite, None, lfs_to_usize_fserror(res));
+        if let Ok(sz) = res {
+            self.record_io(|stats| stats.record_write(sz));
+        }
+        res
     }
 
     /// Change position of subsequent read / write in file.
     pub fn file_seek(
         &mut self,
-        file: &mut File,
+        file: &mut File<PROG_SIZE>,
         off: isize,
         whence: Whence,
     ) -> Result<usize, FsError> {
+        self.begin_op();
         let res = unsafe {
             lfs::lfs_file_seek(&mut self.lfs, &mut file.inner, off as i32, whence as i32)
         };
-        lfs_to_usize_fserror(res)
+        self.finish(Operation::FileSeek, None, lfs_to_usize_fserror(res))
     }
 
-    pub fn file_truncate(&mut self, file: &mut File, size: usize) -> Result<(), FsError> {
+    pub fn file_truncate(
+        &mut self,
+        file: &mut File<PROG_SIZE>,
+        size: usize,
+    ) -> Result<(), FsError> {
+        self.begin_op();
         let res = unsafe { lfs::lfs_file_truncate(&mut self.lfs, &mut file.inner, size as u32) };
-        lfs_to_fserror(res)
+        self.finish(Operation::FileTruncate, None, lfs_to_fserror(res))
     }
 
     /// Tell current position of handle within the file.
-    pub fn file_tell(&mut self, file: &mut File) -> Result<usize, FsError> {
+    pub fn file_tell(&mut self, file: &mut File<PROG_SIZE>) -> Result<usize, FsError> {
+        self.begin_op();
         let res = unsafe { lfs::lfs_file_tell(&mut self.lfs, &mut file.inner) };
-        lfs_to_usize_fserror(res)
+        self.finish(Operation::FileTell, None, lfs_to_usize_fserror(res))
     }
 
     /// Rewind file handle to the start of the file.
-    pub fn file_rewind(&mut self, file: &mut File) -> Result<(), FsError> {
+    pub fn file_rewind(&mut self, file: &mut File<PROG_SIZE>) -> Result<(), FsError> {
+        self.begin_op();
         let res = unsafe { lfs::lfs_file_rewind(&mut self.lfs, &mut file.inner) };
-        lfs_to_fserror(res)
+        self.finish(Operation::FileRewind, None, lfs_to_fserror(res))
     }
 
     /// Return total number of bytes in file.
-    pub fn file_size(&mut self, file: &mut File) -> Result<usize, FsError> {
+    pub fn file_size(&mut self, file: &mut File<PROG_SIZE>) -> Result<usize, FsError> {
+        self.begin_op();
         let res = unsafe { lfs::lfs_file_size(&mut self.lfs, &mut file.inner) };
-        lfs_to_usize_fserror(res)
+        self.finish(Operation::FileSize, None, lfs_to_usize_fserror(res))
     }
 
-    /// Create a new directory.
-    pub fn mkdir(&mut self, path: &str) -> Result<(), FsError> {
-        let mut cstr_path = [0u8; NAME_MAX_LEN + 1];
-        let len = cmp::min(NAME_MAX_LEN, path.len());
-        cstr_path[..len].copy_from_slice(&path.as_bytes()[..len]);
+    /// Fail with `Badf`, recorded as a failure of `operation`, unless `file` is open on this
+    /// filesystem. littlefs keeps the files open on a filesystem in a list, which is searched
+    /// for the file.
+    pub(crate) fn check_open(
+        &mut self,
+        file: &File<PROG_SIZE>,
+        operation: Operation,
+    ) -> Result<(), FsError> {
+        self.begin_op();
+        let mut open = self.lfs.files;
+        while !open.is_null() {
+            if ptr::eq(open, &file.inner) {
+                return Ok(());
+            }
+            open = unsafe { (*open).next };
+        }
+        self.finish(operation, None, Err(FsError::Badf))
+    }
 
-        let res =
-            unsafe { lfs::lfs_mkdir(&mut self.lfs, cstr_path.as_ptr() as *const cty::c_char) };
-        lfs_to_fserror(res)
+    /// Create a new directory.
+    pub fn mkdir<P: AsRef<Path>>(&mut self, path: P) -> Result<(), FsError> {
+        let path = path.as_ref();
+        self.begin_op();
+        let res = path.to_c_path().and_then(|cstr_path| {
+            let res =
+                unsafe { lfs::lfs_mkdir(&mut self.lfs, cstr_path.as_ptr() as *const cty::c_char) };
+            lfs_to_fserror(res)
+        });
+        self.finish(Operation::Mkdir, Some(path.as_str()), res)
     }
 
     /// Open a directory.
-    pub fn dir_open(&mut self, dir: &mut Dir, path: &str) -> Result<(), FsError> {
-        let mut cstr_path = [0u8; NAME_MAX_LEN + 1];
-        let len = cmp::min(NAME_MAX_LEN, path.len());
-        cstr_path[..len].copy_from_slice(&path.as_bytes()[..len]);
-
-        let res = unsafe {
-            lfs::lfs_dir_open(
-                &mut self.lfs,
-                &mut dir.inner,
-                cstr_path.as_ptr() as *const cty::c_char,
-            )
-        };
-        lfs_to_fserror(res)
+    pub fn dir_open<P: AsRef<Path>>(&mut self, dir: &mut Dir, path: P) -> Result<(), FsError> {
+        let path = path.as_ref();
+        self.begin_op();
+        let res = path.to_c_path().and_then(|cstr_path| {
+            let res = unsafe {
+                lfs::lfs_dir_open(
+                    &mut self.lfs,
+                    &mut dir.inner,
+                    cstr_path.as_ptr() as *const cty::c_char,
+                )
+            };
+            lfs_to_fserror(res)
+        });
+        self.finish(Operation::DirOpen, Some(path.as_str()), res)
     }
 
     /// Close a directory.
-    pub fn dir_close(&mut self, mut dir: Dir) -> Result<(), FsError> {
+    pub fn dir_close(&mut self, dir: &mut Dir) -> Result<(), FsError> {
+        self.begin_op();
         let res = unsafe { lfs::lfs_dir_close(&mut self.lfs, &mut dir.inner) };
-        lfs_to_fserror(res)
+        self.finish(Operation::DirClose, None, lfs_to_fserror(res))
     }
 
     /// Read contents of a directory.
     pub fn dir_read(&mut self, dir: &mut Dir) -> Result<Option<Info>, FsError> {
+        self.begin_op();
         let mut lfs_info = unsafe { mem::uninitialized() };
         let res = unsafe { lfs::lfs_dir_read(&mut self.lfs, &mut dir.inner, &mut lfs_info) };
-        let err = lfs_to_fserror(res);
-        match err {
-            Err(FsError::Unknown(1)) => {
-                return Ok(Some(Info::from_lfs_info(lfs_info)));
-            }
-            Ok(()) => {
-                return Ok(None);
-            }
-            Err(err) => {
-                return Err(err);
-            }
+        // A positive result reports an entry, zero the end of the directory.
+        match self.finish(Operation::DirRead, None, lfs_to_usize_fserror(res))? {
+            0 => Ok(None),
+            _ => Ok(Some(Info::from_lfs_info(lfs_info))),
         }
     }
 
     /// Change the position within the directory.
     pub fn dir_seek(&mut self, dir: &mut Dir, offset: isize) -> Result<(), FsError> {
+        self.begin_op();
         let res = unsafe { lfs::lfs_dir_seek(&mut self.lfs, &mut dir.inner, offset as u32) };
-        lfs_to_fserror(res)
+        self.finish(Operation::DirSeek, None, lfs_to_fserror(res))
     }
 
     /// Report position within the directory.
     pub fn dir_tell(&mut self, dir: &mut Dir) -> Result<usize, FsError> {
+        self.begin_op();
         let res = unsafe { lfs::lfs_dir_tell(&mut self.lfs, &mut dir.inner) };
-        lfs_to_usize_fserror(res)
+        self.finish(Operation::DirTell, None, lfs_to_usize_fserror(res))
     }
 
     /// Rewrite directory handle back to start of directory.
     pub fn dir_rewind(&mut self, dir: &mut Dir) -> Result<(), FsError> {
+        self.begin_op();
         let res = unsafe { lfs::lfs_dir_rewind(&mut self.lfs, &mut dir.inner) };
-        lfs_to_fserror(res)
+        self.finish(Operation::DirRewind, None, lfs_to_fserror(res))
+    }
+}
+
+/// Glue between the LittleFS and the C library.
+impl<
+        T: Storage,
+        const READ_SIZE: usize,
+        const PROG_SIZE: usize,
+        const LOOKAHEAD_SIZE: usize,
+        S: MountState,
+    > LittleFs<T, READ_SIZE, PROG_SIZE, LOOKAHEAD_SIZE, S>
+{
+    /// Start accounting storage operations for a new filesystem call.
+    fn begin_op(&mut self) {
+        if S::MOUNTED {
+            self.rebind();
+        }
+        self.last_io_stats = IoStats::default();
+        self.storage_error = None;
+        self.last_error = None;
+    }
+
+    /// Record the context of a failed filesystem call.
+    fn finish<R>(
+        &mut self,
+        operation: Operation,
+        path: Option<&str>,
+        res: Result<R, FsError>,
+    ) -> Result<R, FsError> {
+        if let Err(error) = res {
+            self.last_error = Some(ErrorContext::new(
+                error,
+                operation,
+                path,
+                self.storage_error,
+            ));
+        }
+        res
+    }
+
+    /// Record the context of a failed call built on other filesystem calls, unless the failure
+    /// was returned by one of those calls and already recorded.
+    fn finish_composite<R>(
+        &mut self,
+        operation: Operation,
+        path: &str,
+        res: Result<R, FsError>,
+    ) -> Result<R, FsError> {
+        if let Err(error) = res {
+            match self.last_error {
+                Some(ref context) if context.error() == error => (),
+                _ => {
+                    self.last_error = Some(ErrorContext::new(error, operation, Some(path), None));
+                }
+            }
+        }
+        res
+    }
+
+    /// Record a storage operation into the per-call and cumulative statistics, if enabled.
+    fn record_io<F: Fn(&mut IoStats)>(&mut self, record: F) {
+        if let Some(stats) = self.io_stats.as_mut() {
+            record(stats);
+            record(&mut self.last_io_stats);
+        }
     }
 
     /// Create instance of lfs configuration.
     fn create_lfs_config(&mut self) -> lfs::lfs_config {
         lfs::lfs_config {
             context: self as *mut _ as *mut cty::c_void,
-            read: Some(<LittleFs<T>>::lfs_config_read),
-            prog: Some(<LittleFs<T>>::lfs_config_prog),
-            erase: Some(<LittleFs<T>>::lfs_config_erase),
-            sync: Some(<LittleFs<T>>::lfs_config_sync),
-            read_size: READ_SIZE as u32,
-            prog_size: PROG_SIZE as u32,
+            read: Some(Self::lfs_config_read),
+            prog: Some(Self::lfs_config_prog),
+            erase: Some(Self::lfs_config_erase),
+            sync: Some(Self::lfs_config_sync),
+            read_size: self.config.read_size as u32,
+            prog_size: self.config.prog_size as u32,
             block_size: BLOCK_SIZE as u32,
             block_count: BLOCK_COUNT as u32,
-            lookahead: LOOKAHEAD as u32,
-            read_buffer: (&mut self.read_buffer) as *mut _ as *mut cty::c_void,
-            prog_buffer: (&mut self.prog_buffer) as *mut _ as *mut cty::c_void,
-            lookahead_buffer: (&mut self.lookahead_buffer) as *mut _ as *mut cty::c_void,
+            lookahead: self.config.lookahead as u32,
+            read_buffer: buffer_or_null(&mut self.read_buffer, self.config.read_size),
+            prog_buffer: buffer_or_null(&mut self.prog_buffer, self.config.prog_size),
+            lookahead_buffer: buffer_or_null(&mut self.lookahead_buffer, self.config.lookahead / 8),
             file_buffer: core::ptr::null_mut(),
         }
     }
@@ -557,15 +1446,22 @@ impl<T: Storage> LittleFs<T> {
         buffer: *mut cty::c_void,
         size: lfs::lfs_size_t,
     ) -> cty::c_int {
-        let littlefs: &mut LittleFs<T> = unsafe { mem::transmute((*c).context) };
+        let littlefs: &mut Self = unsafe { mem::transmute((*c).context) };
         assert!(!c.is_null());
-        let block_size = unsafe { c.read().block_size };
-        let off = (block * block_size + off) as usize;
+        let off = match storage_offset(unsafe { &*c }, block, off, size) {
+            Some(off) => off,
+            None => return lfs::lfs_error_LFS_ERR_CORRUPT,
+        };
         let buf: &mut [u8] = unsafe { slice::from_raw_parts_mut(buffer as *mut u8, size as usize) };
 
-        // TODO
-        littlefs.storage.read(off, buf).unwrap();
-        0
+        littlefs.record_io(|stats| stats.record_read(size as usize));
+        match littlefs.storage.read(off, buf) {
+            Ok(_) => lfs::lfs_error_LFS_ERR_OK,
+            Err(err) => {
+                littlefs.storage_error.get_or_insert(err);
+                lfs::lfs_error_LFS_ERR_IO
+            }
+        }
     }
 
     /// C callback interface used by LittleFS to program data with the lower level system below the
@@ -577,15 +1473,22 @@ impl<T: Storage> LittleFs<T> {
         buffer: *const cty::c_void,
         size: lfs::lfs_size_t,
     ) -> cty::c_int {
-        let littlefs: &mut LittleFs<T> = unsafe { mem::transmute((*c).context) };
+        let littlefs: &mut Self = unsafe { mem::transmute((*c).context) };
         assert!(!c.is_null());
-        let block_size = unsafe { c.read().block_size };
-        let off = (block * block_size + off) as usize;
+        let off = match storage_offset(unsafe { &*c }, block, off, size) {
+            Some(off) => off,
+            None => return lfs::lfs_error_LFS_ERR_CORRUPT,
+        };
         let buf: &[u8] = unsafe { slice::from_raw_parts(buffer as *const u8, size as usize) };
 
-        // TODO
-        littlefs.storage.write(off, buf).unwrap();
-        0
+        littlefs.record_io(|stats| stats.record_prog(size as usize));
+        match littlefs.storage.write(off, buf) {
+            Ok(_) => lfs::lfs_error_LFS_ERR_OK,
+            Err(err) => {
+                littlefs.storage_error.get_or_insert(err);
+                lfs::lfs_error_LFS_ERR_IO
+            }
+        }
     }
 
     /// C callback interface used by LittleFS to erase data with the lower level system below the
@@ -594,12 +1497,20 @@ impl<T: Storage> LittleFs<T> {
         c: *const lfs::lfs_config,
         block: lfs::lfs_block_t,
     ) -> cty::c_int {
-        let littlefs: &mut LittleFs<T> = unsafe { mem::transmute((*c).context) };
-        let off = block as usize * BLOCK_SIZE;
+        let littlefs: &mut Self = unsafe { mem::transmute((*c).context) };
+        let off = match storage_offset(unsafe { &*c }, block, 0, BLOCK_SIZE as u32) {
+            Some(off) => off,
+            None => return lfs::lfs_error_LFS_ERR_CORRUPT,
+        };
 
-        // TODO
-        littlefs.storage.erase(off, BLOCK_SIZE).unwrap();
-        0
+        littlefs.record_io(|stats| stats.record_erase(BLOCK_SIZE));
+        match littlefs.storage.erase(off, BLOCK_SIZE) {
+            Ok(_) => lfs::lfs_error_LFS_ERR_OK,
+            Err(err) => {
+                littlefs.storage_error.get_or_insert(err);
+                lfs::lfs_error_LFS_ERR_IO
+            }
+        }
     }
 
     /// C callback interface used by LittleFS to sync data with the lower level interface below the
@@ -619,7 +1530,8 @@ mod tests {
     const ERASE_VALUE: u8 = 0xFF;
 
     const STORAGE_SIZE: usize = 131072;
-    struct RamStorage {
+    #[derive(Clone)]
+    pub struct RamStorage {
         buf: [u8; STORAGE_SIZE],
     }
 
@@ -660,6 +1572,23 @@ mod tests {
         }
     }
 
+    /// Storage which fails every operation.
+    pub struct FailingStorage;
+
+    impl Storage for FailingStorage {
+        fn read(&self, off: usize, buf: &mut [u8]) -> Result<usize, FsError> {
+            Err(FsError::Io)
+        }
+
+        fn write(&mut self, off: usize, data: &[u8]) -> Result<usize, FsError> {
+            Err(FsError::Io)
+        }
+
+        fn erase(&mut self, off: usize, len: usize) -> Result<usize, FsError> {
+            Err(FsError::Io)
+        }
+    }
+
     #[test]
     fn test_create_littlefs() {
         let storage = RamStorage::default();
@@ -672,7 +1601,322 @@ mod tests {
         let storage = RamStorage::default();
         let mut lfs = LittleFs::new(storage);
         lfs.format().unwrap();
-        lfs.mount().unwrap();
+        let lfs = lfs.mount().unwrap();
+        lfs.unmount().unwrap();
+    }
+
+    #[test]
+    fn test_mount_blank_storage() {
+        let storage = RamStorage::default();
+        let lfs = LittleFs::new(storage);
+        assert_eq!(lfs.mount().err().unwrap().error, FsError::Corrupt);
+    }
+
+    #[test]
+    fn test_storage_error() {
+        let mut lfs = LittleFs::new(FailingStorage);
+        assert_eq!(lfs.format().unwrap_err(), FsError::Io);
+        assert_eq!(lfs.mount().err().unwrap().error, FsError::Io);
+    }
+
+    #[test]
+    fn test_mount_or_format() {
+        let lfs = LittleFs::new(RamStorage::default());
+        let (mut lfs, outcome) = lfs.mount_or_format().unwrap();
+        assert_eq!(outcome, MountOutcome::Formatted(FormatReason::Blank));
+        assert!(outcome.formatted());
+        lfs.mkdir("/keep").unwrap();
+        let storage = lfs.unmount().unwrap();
+
+        let (mut lfs, outcome) = LittleFs::new(storage).mount_or_format().unwrap();
+        assert_eq!(outcome, MountOutcome::Mounted);
+        let mut info = Info::default();
+        lfs.stat("/keep", &mut info).unwrap();
+        let mut storage = lfs.unmount().unwrap();
+
+        // Damage both superblocks.
+        let garbage: [u8; 64] = core::array::from_fn(|i| i as u8);
+        storage.write(0, &garbage).unwrap();
+        storage.write(BLOCK_SIZE, &garbage).unwrap();
+
+        let lfs = LittleFs::new(storage);
+        let mut asked = None;
+        let err = lfs
+            .mount_or_format_with(|reason| {
+                asked = Some(reason);
+                false
+            })
+            .err()
+            .unwrap();
+        assert_eq!(err.error, FsError::Corrupt);
+        assert_eq!(asked, Some(FormatReason::Damaged));
+        let mut buf = [0u8; 64];
+        err.fs.storage().read(0, &mut buf).unwrap();
+        assert_eq!(buf, garbage);
+
+        let (mut lfs, outcome) = err.fs.mount_or_format().unwrap();
+        assert_eq!(outcome, MountOutcome::Formatted(FormatReason::Damaged));
+        assert_eq!(lfs.stat("/keep", &mut info), Err(FsError::Noent));
+        lfs.unmount().unwrap();
+    }
+
+    #[test]
+    fn test_recover_storage() {
+        let mut lfs = LittleFs::new(RamStorage::default());
+        lfs.format().unwrap();
+        let mut lfs = lfs.mount().unwrap();
+        lfs.mkdir("/foo").unwrap();
+
+        // The storage can be taken back without an explicit unmount.
+        let mut lfs = LittleFs::new(lfs.into_inner()).mount().unwrap();
+        lfs.mkdir("/bar").unwrap();
+        drop(lfs);
+
+        // Dropping a mounted filesystem unmounts it, leaving the storage ready to be mounted
+        // again with everything written before.
+        struct Lent<'a>(&'a mut RamStorage);
+
+        impl<'a> Storage for Lent<'a> {
+            fn read(&self, off: usize, buf: &mut [u8]) -> Result<usize, FsError> {
+                self.0.read(off, buf)
+            }
+
+            fn write(&mut self, off: usize, data: &[u8]) -> Result<usize, FsError> {
+                self.0.write(off, data)
+            }
+
+            fn erase(&mut self, off: usize, len: usize) -> Result<usize, FsError> {
+                self.0.erase(off, len)
+            }
+        }
+
+        let mut storage = RamStorage::default();
+        let mut lfs = LittleFs::new(Lent(&mut storage));
+        lfs.format().unwrap();
+        let mut lfs = lfs.mount().unwrap();
+        let mut file = File::default();
+        lfs.file_open(
+            &mut file,
+            "/kept.txt",
+            FileOpenFlags::WRONLY | FileOpenFlags::CREAT,
+        )
+        .unwrap();
+        lfs.file_write(&mut file, b"kept").unwrap();
+        lfs.file_close(&mut file).unwrap();
+        drop(lfs);
+
+        let mut lfs = LittleFs::new(storage).mount().unwrap();
+        lfs.file_open(&mut file, "/kept.txt", FileOpenFlags::RDONLY)
+            .unwrap();
+        let mut buf = [0u8; 8];
+        let sz = lfs.file_read(&mut file, &mut buf).unwrap();
+        assert_eq!(&buf[..sz], b"kept");
+        lfs.file_close(&mut file).unwrap();
+        lfs.unmount().unwrap();
+
+        // An unmounted filesystem hands back its storage as is.
+        let storage = LittleFs::new(RamStorage::default()).into_inner();
+        let err = LittleFs::new(storage).mount().err().unwrap();
+        assert_eq!(err.error, FsError::Corrupt);
+    }
+
+    #[test]
+    fn test_storage_access() {
+        fn mounted() -> LittleFs<RamStorage> {
+            let mut lfs = LittleFs::new(RamStorage::default());
+            lfs.format().unwrap();
+            lfs.mount().unwrap()
+        }
+
+        // The filesystem keeps working after being moved out of the function that mounted it.
+        let mut lfs = mounted();
+        lfs.mkdir("/foo").unwrap();
+        let mut lfs = lfs.into_unmounted().unwrap();
+        let mut buf = [0u8; 8];
+        lfs.storage().read(0, &mut buf).unwrap();
+        assert!(buf.iter().any(|&b| b != 0xff));
+
+        // Erasing the superblocks is only possible while the filesystem is unmounted.
+        lfs.storage_mut().erase(0, 2 * BLOCK_SIZE).unwrap();
+        let mut lfs = lfs.mount().err().unwrap().fs;
+        lfs.format().unwrap();
+        let mut lfs = lfs.mount().unwrap();
+        let mut info = Info::default();
+        assert_eq!(lfs.stat("/foo", &mut info), Err(FsError::Noent));
+        lfs.unmount().unwrap();
+    }
+
+    #[test]
+    fn test_move_with_open_files() {
+        extern crate std;
+        use std::boxed::Box;
+
+        let mut lfs = LittleFs::new(RamStorage::default());
+        lfs.format().unwrap();
+        let mut lfs = lfs.mount().unwrap();
+        let mut reader = File::default();
+        let mut writer = File::default();
+        lfs.mkdir("/logs").unwrap();
+        lfs.file_open(
+            &mut writer,
+            "/logs/a.txt",
+            FileOpenFlags::WRONLY | FileOpenFlags::CREAT,
+        )
+        .unwrap();
+        lfs.file_write(&mut writer, b"first").unwrap();
+        lfs.file_sync(&mut writer).unwrap();
+        lfs.file_write(&mut writer, b" second").unwrap();
+        lfs.file_open(&mut reader, "/logs/a.txt", FileOpenFlags::RDONLY)
+            .unwrap();
+        let mut buf = [0u8; 3];
+        assert_eq!(lfs.file_read(&mut reader, &mut buf).unwrap(), 3);
+        assert_eq!(&buf, b"fir");
+
+        // Moving the filesystem while both files are open and part of the write is still
+        // cached must keep the caches, lookahead and open files working at the new address.
+        let mut boxed = Box::new(lfs);
+        let mut buf = [0u8; 8];
+        let sz = boxed.file_read(&mut reader, &mut buf).unwrap();
+        assert_eq!(&buf[..sz], b"st");
+        boxed.file_write(&mut writer, b" third").unwrap();
+        boxed.file_close(&mut writer).unwrap();
+        boxed.file_close(&mut reader).unwrap();
+        boxed.mkdir("/more").unwrap();
+
+        let mut lfs = *boxed;
+        lfs.file_open(&mut reader, "/logs/a.txt", FileOpenFlags::RDONLY)
+            .unwrap();
+        let mut buf = [0u8; 32];
+        let sz = lfs.file_read(&mut reader, &mut buf).unwrap();
+        assert_eq!(&buf[..sz], b"first second third");
+        lfs.file_close(&mut reader).unwrap();
+
+        let storage = lfs.unmount().unwrap();
+        let mut lfs = LittleFs::new(storage).mount().unwrap();
+        lfs.file_open(&mut reader, "/logs/a.txt", FileOpenFlags::RDONLY)
+            .unwrap();
+        let sz = lfs.file_read(&mut reader, &mut buf).unwrap();
+        assert_eq!(&buf[..sz], b"first second third");
+        lfs.file_close(&mut reader).unwrap();
+        lfs.remove("/more").unwrap();
+        lfs.unmount().unwrap();
+    }
+
+    #[test]
+    fn test_mount_or_format_io_error() {
+        let lfs = LittleFs::new(FailingStorage);
+        let err = lfs.mount_or_format_with(|_| panic!()).err().unwrap();
+        assert_eq!(err.error, FsError::Io);
+    }
+
+    #[test]
+    fn test_storage_offset() {
+        let mut lfs = LittleFs::new(RamStorage::default());
+        let cfg = lfs.create_lfs_config();
+        assert_eq!(storage_offset(&cfg, 1, 16, 16), Some(BLOCK_SIZE + 16));
+        assert_eq!(storage_offset(&cfg, BLOCK_COUNT as u32, 0, 16), None);
+        assert_eq!(storage_offset(&cfg, 0, BLOCK_SIZE as u32 - 8, 16), None);
+        assert_eq!(storage_offset(&cfg, 0, u32::MAX, 16), None);
+    }
+
+    #[test]
+    fn test_config_validation() {
+        let invalid = [
+            Config {
+                read_size: 300,
+                ..Config::default()
+            },
+            Config {
+                prog_size: 0,
+                ..Config::default()
+            },
+            Config {
+                lookahead: 48,
+                ..Config::default()
+            },
+        ];
+        for config in invalid.iter() {
+            let mut lfs = LittleFs::with_config(RamStorage::default(), *config);
+            assert_eq!(lfs.format(), Err(FsError::Inval));
+            assert_eq!(lfs.mount().err().unwrap().error, FsError::Inval);
+        }
+
+        let config = Config {
+            read_size: 128,
+            prog_size: 128,
+            lookahead: 32,
+            ..Config::default()
+        };
+        let mut lfs = LittleFs::with_config(RamStorage::default(), config);
+        lfs.format().unwrap();
+        let lfs = lfs.mount().unwrap();
+        assert_eq!(lfs.config(), &config);
+        lfs.unmount().unwrap();
+    }
+
+    #[test]
+    #[cfg(not(feature = "alloc"))]
+    fn test_config_exceeds_static_buffers() {
+        let config = Config {
+            prog_size: DEFAULT_PROG_SIZE * 2,
+            ..Config::default()
+        };
+        let mut lfs = LittleFs::with_config(RamStorage::default(), config);
+        assert_eq!(lfs.format(), Err(FsError::Nomem));
+    }
+
+    #[test]
+    #[cfg(feature = "alloc")]
+    fn test_dynamic_buffers() {
+        let config = Config {
+            read_size: DEFAULT_READ_SIZE * 2,
+            prog_size: DEFAULT_PROG_SIZE * 4,
+            lookahead: DEFAULT_LOOKAHEAD_SIZE * 16,
+            ..Config::default()
+        };
+        let mut lfs = LittleFs::with_config(RamStorage::default(), config);
+        lfs.format().unwrap();
+        let mut lfs = lfs.mount().unwrap();
+        let data = [0x5a; 3000];
+        let mut file = File::default();
+        lfs.file_open(
+            &mut file,
+            "/big.bin",
+            FileOpenFlags::WRONLY | FileOpenFlags::CREAT,
+        )
+        .unwrap();
+        assert_eq!(lfs.file_write(&mut file, &data).unwrap(), data.len());
+        lfs.file_close(&mut file).unwrap();
+
+        let mut buf = [0u8; 3000];
+        lfs.file_open(&mut file, "/big.bin", FileOpenFlags::RDONLY)
+            .unwrap();
+        assert_eq!(lfs.file_read(&mut file, &mut buf).unwrap(), buf.len());
+        assert!(buf.iter().all(|&b| b == 0x5a));
+        lfs.file_close(&mut file).unwrap();
+        lfs.unmount().unwrap();
+    }
+
+    #[test]
+    fn test_sized_buffers() {
+        let mut lfs: LittleFs<RamStorage, 64, 128, 8, Unmounted> =
+            LittleFs::new_sized(RamStorage::default());
+        assert_eq!(lfs.config().lookahead, 64);
+        lfs.format().unwrap();
+        let mut lfs = lfs.mount().unwrap();
+        let mut file: File<128> = File::default();
+        lfs.file_open(
+            &mut file,
+            "/small.txt",
+            FileOpenFlags::RDWR | FileOpenFlags::CREAT,
+        )
+        .unwrap();
+        lfs.file_write(&mut file, &[0xa5; 300]).unwrap();
+        lfs.file_rewind(&mut file).unwrap();
+        let mut buf = [0u8; 300];
+        assert_eq!(lfs.file_read(&mut file, &mut buf).unwrap(), buf.len());
+        assert!(buf.iter().all(|&b| b == 0xa5));
+        lfs.file_close(&mut file).unwrap();
         lfs.unmount().unwrap();
     }
 
@@ -681,7 +1925,7 @@ mod tests {
         let storage = RamStorage::default();
         let mut lfs = LittleFs::new(storage);
         lfs.format().unwrap();
-        lfs.mount().unwrap();
+        let mut lfs = lfs.mount().unwrap();
         lfs.mkdir("/foo").unwrap();
 
         let mut dir = Default::default();
@@ -696,7 +1940,7 @@ mod tests {
         assert!(info.is_none());
         let info = lfs.dir_read(&mut dir).unwrap();
         assert!(info.is_none());
-        lfs.dir_close(dir).unwrap();
+        lfs.dir_close(&mut dir).unwrap();
 
         lfs.unmount().unwrap();
     }
@@ -706,7 +1950,7 @@ mod tests {
         let storage = RamStorage::default();
         let mut lfs = LittleFs::new(storage);
         lfs.format().unwrap();
-        lfs.mount().unwrap();
+        let mut lfs = lfs.mount().unwrap();
         let mut file = Default::default();
         lfs.file_open(
             &mut file,
@@ -722,7 +1966,7 @@ mod tests {
         let storage = RamStorage::default();
         let mut lfs = LittleFs::new(storage);
         lfs.format().unwrap();
-        lfs.mount().unwrap();
+        let mut lfs = lfs.mount().unwrap();
         let mut file = Default::default();
         lfs.file_open(
             &mut file,
@@ -732,7 +1976,7 @@ mod tests {
         .unwrap();
         let sz = lfs.file_write(&mut file, b"hello world!").unwrap();
         assert_ne!(sz, 0);
-        lfs.file_close(file).unwrap();
+        lfs.file_close(&mut file).unwrap();
         lfs.unmount().unwrap();
     }
 
@@ -741,7 +1985,7 @@ mod tests {
         let storage = RamStorage::default();
         let mut lfs = LittleFs::new(storage);
         lfs.format().unwrap();
-        lfs.mount().unwrap();
+        let mut lfs = lfs.mount().unwrap();
         let mut file = Default::default();
         lfs.file_open(
             &mut file,
@@ -755,7 +1999,7 @@ mod tests {
         let file_sz = lfs.file_size(&mut file).unwrap();
         assert_eq!(file_sz, write_sz);
 
-        lfs.file_close(file).unwrap();
+        lfs.file_close(&mut file).unwrap();
 
         let mut file = Default::default();
         lfs.file_open(&mut file, "/foo.txt", FileOpenFlags::RDWR)
@@ -763,7 +2007,7 @@ mod tests {
         let mut buf = [0u8; 32];
         let read_sz = lfs.file_read(&mut file, &mut buf).unwrap();
         assert_ne!(read_sz, 0);
-        lfs.file_close(file).unwrap();
+        lfs.file_close(&mut file).unwrap();
         lfs.unmount().unwrap();
 
         assert_eq!(read_sz, write_sz);
@@ -776,7 +2020,7 @@ mod tests {
         let storage = RamStorage::default();
         let mut lfs = LittleFs::new(storage);
         lfs.format().unwrap();
-        lfs.mount().unwrap();
+        let mut lfs = lfs.mount().unwrap();
         let mut file = Default::default();
         lfs.file_open(
             &mut file,
@@ -786,7 +2030,7 @@ mod tests {
         .unwrap();
         let write_sz = lfs.file_write(&mut file, b"hello world!").unwrap();
         assert_ne!(write_sz, 0);
-        lfs.file_close(file).unwrap();
+        lfs.file_close(&mut file).unwrap();
 
         let mut file = Default::default();
         lfs.file_open(&mut file, "/foo.txt", FileOpenFlags::RDWR)
@@ -796,7 +2040,7 @@ mod tests {
         let mut buf = [0u8; 32];
         let read_sz = lfs.file_read(&mut file, &mut buf).unwrap();
         assert_ne!(read_sz, 0);
-        lfs.file_close(file).unwrap();
+        lfs.file_close(&mut file).unwrap();
 
         lfs.unmount().unwrap();
 
@@ -809,7 +2053,7 @@ mod tests {
         let storage = RamStorage::default();
         let mut lfs = LittleFs::new(storage);
         lfs.format().unwrap();
-        lfs.mount().unwrap();
+        let mut lfs = lfs.mount().unwrap();
         let mut file = Default::default();
         lfs.file_open(
             &mut file,
@@ -821,7 +2065,7 @@ mod tests {
         assert_ne!(write_sz, 0);
 
         lfs.file_truncate(&mut file, 0).unwrap();
-        lfs.file_close(file).unwrap();
+        lfs.file_close(&mut file).unwrap();
 
         let mut file = Default::default();
         lfs.file_open(&mut file, "/foo.txt", FileOpenFlags::RDWR)
@@ -829,7 +2073,7 @@ mod tests {
         let mut buf = [0u8; 32];
         let read_sz = lfs.file_read(&mut file, &mut buf).unwrap();
         assert_eq!(read_sz, 0);
-        lfs.file_close(file).unwrap();
+        lfs.file_close(&mut file).unwrap();
 
         lfs.unmount().unwrap();
     }
@@ -839,7 +2083,7 @@ mod tests {
         let storage = RamStorage::default();
         let mut lfs = LittleFs::new(storage);
         lfs.format().unwrap();
-        lfs.mount().unwrap();
+        let mut lfs = lfs.mount().unwrap();
         let mut file = Default::default();
         lfs.file_open(
             &mut file,
@@ -857,7 +2101,7 @@ mod tests {
         let tell_sz = lfs.file_tell(&mut file).unwrap();
         assert_eq!(tell_sz, 0);
 
-        lfs.file_close(file).unwrap();
+        lfs.file_close(&mut file).unwrap();
         lfs.unmount().unwrap();
     }
 
@@ -866,7 +2110,7 @@ mod tests {
         let storage = RamStorage::default();
         let mut lfs = LittleFs::new(storage);
         lfs.format().unwrap();
-        lfs.mount().unwrap();
+        let mut lfs = lfs.mount().unwrap();
         let mut file = Default::default();
         lfs.file_open(
             &mut file,
@@ -882,7 +2126,7 @@ mod tests {
             let sz = lfs.file_write(&mut file, &bytes).unwrap();
             assert_eq!(sz, 256);
         }
-        lfs.file_close(file).unwrap();
+        lfs.file_close(&mut file).unwrap();
 
         lfs.unmount().unwrap();
     }
@@ -892,7 +2136,7 @@ mod tests {
         let storage = RamStorage::default();
         let mut lfs = LittleFs::new(storage);
         lfs.format().unwrap();
-        lfs.mount().unwrap();
+        let mut lfs = lfs.mount().unwrap();
         let mut file = Default::default();
         lfs.file_open(
             &mut file,
@@ -908,7 +2152,7 @@ mod tests {
             let sz = lfs.file_write(&mut file, &bytes).unwrap();
             assert_eq!(sz, 256);
         }
-        lfs.file_close(file).unwrap();
+        lfs.file_close(&mut file).unwrap();
 
         lfs.remove("/foo.txt").unwrap();
         lfs.unmount().unwrap();
@@ -919,7 +2163,7 @@ mod tests {
         let storage = RamStorage::default();
         let mut lfs = LittleFs::new(storage);
         lfs.format().unwrap();
-        lfs.mount().unwrap();
+        let mut lfs = lfs.mount().unwrap();
         let mut file = Default::default();
         lfs.file_open(
             &mut file,
@@ -935,7 +2179,7 @@ mod tests {
             let sz = lfs.file_write(&mut file, &bytes).unwrap();
             assert_eq!(sz, 256);
         }
-        lfs.file_close(file).unwrap();
+        lfs.file_close(&mut file).unwrap();
 
         let mut dir = Default::default();
         lfs.dir_open(&mut dir, "/").unwrap();
@@ -947,7 +2191,7 @@ mod tests {
         assert_eq!(info.unwrap().name, "foo.txt");
         let info = lfs.dir_read(&mut dir).unwrap();
         assert!(info.is_none());
-        lfs.dir_close(dir).unwrap();
+        lfs.dir_close(&mut dir).unwrap();
 
         lfs.rename("/foo.txt", "/bar.txt").unwrap();
 
@@ -961,7 +2205,7 @@ mod tests {
         assert_eq!(info.unwrap().name, "bar.txt");
         let info = lfs.dir_read(&mut dir).unwrap();
         assert!(info.is_none());
-        lfs.dir_close(dir).unwrap();
+        lfs.dir_close(&mut dir).unwrap();
 
         lfs.unmount().unwrap();
     }
@@ -971,7 +2215,7 @@ mod tests {
         let storage = RamStorage::default();
         let mut lfs = LittleFs::new(storage);
         lfs.format().unwrap();
-        lfs.mount().unwrap();
+        let mut lfs = lfs.mount().unwrap();
         let mut file = Default::default();
         lfs.file_open(
             &mut file,
@@ -987,7 +2231,7 @@ mod tests {
             let sz = lfs.file_write(&mut file, &bytes).unwrap();
             assert_eq!(sz, 256);
         }
-        lfs.file_close(file).unwrap();
+        lfs.file_close(&mut file).unwrap();
 
         let mut file = Default::default();
         lfs.file_open(&mut file, "/foo.txt", FileOpenFlags::RDWR)
@@ -1003,7 +2247,7 @@ mod tests {
                 assert_eq!(buf[i], bytes[i]);
             }
         }
-        lfs.file_close(file).unwrap();
+        lfs.file_close(&mut file).unwrap();
 
         lfs.unmount().unwrap();
     }
@@ -1024,4 +2268,35 @@ mod tests {
         assert_eq!(info.size, 4);
         assert_eq!(info.name, "/foo.txt");
     }
+
+    #[test]
+    fn test_filename() {
+        use core::convert::TryFrom;
+        extern crate std;
+        use std::string::ToString;
+
+        let name = Filename::try_from("foo.txt").unwrap();
+        assert_eq!(name.len(), 7);
+        assert_eq!(name.as_bytes(), b"foo.txt");
+        assert_eq!(name.as_str(), Ok("foo.txt"));
+        assert_eq!(name.to_string(), "foo.txt");
+        assert_eq!(name, "foo.txt");
+        assert_ne!(name, "foo");
+        assert_ne!(name, "foo.txt.bak");
+        assert!(name < Filename::try_from("foo.txz").unwrap());
+        assert!(Filename::default().is_empty());
+
+        let longest = [b'a'; NAME_MAX_LEN];
+        let longest = core::str::from_utf8(&longest).unwrap();
+        assert_eq!(Filename::try_from(longest).unwrap(), longest);
+        let too_long = std::format!("{}a", longest);
+        assert_ne!(Filename::try_from(longest).unwrap(), too_long.as_str());
+        assert_eq!(Filename::try_from(too_long.as_str()), Err(FsError::Inval));
+        assert_eq!(Filename::try_from("a\0b"), Err(FsError::Inval));
+
+        let mut invalid = Filename::default();
+        invalid.0[..3].copy_from_slice(b"a\xffb");
+        assert!(invalid.as_str().is_err());
+        assert_eq!(invalid.to_string(), "a\u{fffd}b");
+    }
 }
@@ -0,0 +1,197 @@
+//! Forwarding of C library log messages and assertions into Rust.
+//!
+//! With the `log` or `defmt` feature enabled, littlefs-sys is built with a replacement utility
+//! header whose `LFS_DEBUG`, `LFS_WARN`, `LFS_ERROR` and `LFS_TRACE` macros hand their format
+//! string and arguments to a hook rather than calling printf. The hooks are installed the first
+//! time a filesystem is formatted or mounted. Messages are emitted with the `littlefs` target and
+//! failed assertions panic with the asserted expression and its location.
+//!
+//! The assertion hook is called from C, and a panic cannot unwind through the frames of the C
+//! library, so a failed assertion aborts after the panic handler has reported it, also when
+//! the crate is built with `panic = "unwind"`. It cannot be caught with `catch_unwind`.
+//!
+//! Only the integer conversions used by littlefs are understood; flags, field widths and
+//! precisions are accepted but ignored.
+//!
+//! The `alloc` and `crc` features build littlefs-sys with the same header. Without `log` or
+//! `defmt` the messages are discarded, but failed assertions still panic rather than trap
+//! without a message.
+
+use core::ffi::CStr;
+use core::fmt;
+
+use littlefs_sys as lfs;
+
+/// Target under which messages are emitted to the `log` crate.
+pub const TARGET: &str = "littlefs";
+
+/// Install the log and assertion hooks into the C library.
+pub(crate) fn install() {
+    unsafe {
+        #[cfg(any(feature = "log", feature = "defmt"))]
+        {
+            lfs::lfs_rust_log_hook = Some(log_hook);
+        }
+        lfs::lfs_rust_assert_hook = Some(assert_hook);
+    }
+}
+
+/// Log message of the C library: a printf format string together with its arguments.
+pub(crate) struct Message<'a> {
+    fmt: &'a [u8],
+    args: &'a [u32],
+}
+
+impl<'a> Message<'a> {
+    pub(crate) fn new(fmt: &'a [u8], args: &'a [u32]) -> Self {
+        Message { fmt, args }
+    }
+}
+
+impl<'a> fmt::Display for Message<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let mut args = self.args.iter();
+        let mut rest = self.fmt;
+        while !rest.is_empty() {
+            let literal = rest.iter().position(|&c| c == b'%').unwrap_or(rest.len());
+            write_bytes(f, &rest[..literal])?;
+            rest = &rest[literal..];
+            if rest.is_empty() {
+                break;
+            }
+
+            // Skip the flags, field width, precision and length modifier of the conversion.
+            let mut i = 1;
+            while i < rest.len() && b"-+ #0123456789.hljztLq".contains(&rest[i]) {
+                i += 1;
+            }
+            let conversion = match rest.get(i) {
+                Some(&c) => c,
+                None => {
+                    write_bytes(f, rest)?;
+                    break;
+                }
+            };
+            if conversion == b'%' {
+                f.write_str("%")?;
+            } else if let Some(&arg) = args.next() {
+                match conversion {
+                    b'd' | b'i' => write!(f, "{}", arg as i32)?,
+                    b'x' => write!(f, "{:x}", arg)?,
+                    b'X' => write!(f, "{:X}", arg)?,
+                    b'o' => write!(f, "{:o}", arg)?,
+                    b'p' => write!(f, "{:#x}", arg)?,
+                    b'c' => write!(f, "{}", arg as u8 as char)?,
+                    _ => write!(f, "{}", arg)?,
+                }
+            } else {
+                f.write_str("?")?;
+            }
+            rest = &rest[i + 1..];
+        }
+        Ok(())
+    }
+}
+
+fn write_bytes(f: &mut fmt::Formatter, bytes: &[u8]) -> fmt::Result {
+    match core::str::from_utf8(bytes) {
+        Ok(s) => f.write_str(s),
+        Err(_) => f.write_str("?"),
+    }
+}
+
+/// Borrow a C string as a str, substituting a placeholder for null or invalid strings.
+unsafe fn c_str<'a>(s: *const cty::c_char) -> &'a str {
+    if s.is_null() {
+        return "?";
+    }
+    CStr::from_ptr(s).to_str().unwrap_or("?")
+}
+
+#[cfg(any(feature = "log", feature = "defmt"))]
+unsafe extern "C" fn log_hook(
+    level: cty::c_int,
+    file: *const cty::c_char,
+    line: cty::c_int,
+    fmt: *const cty::c_char,
+    args: *const u32,
+    nargs: usize,
+) {
+    let file = c_str(file);
+    let fmt = if fmt.is_null() {
+        &[][..]
+    } else {
+        CStr::from_ptr(fmt).to_bytes()
+    };
+    let args = if args.is_null() {
+        &[][..]
+    } else {
+        core::slice::from_raw_parts(args, nargs)
+    };
+    let message = Message::new(fmt, args);
+
+    #[cfg(feature = "log")]
+    {
+        let level = match level {
+            lfs::LFS_RUST_LOG_ERROR => log::Level::Error,
+            lfs::LFS_RUST_LOG_WARN => log::Level::Warn,
+            lfs::LFS_RUST_LOG_DEBUG => log::Level::Debug,
+            lfs::LFS_RUST_LOG_TRACE => log::Level::Trace,
+            _ => log::Level::Info,
+        };
+        log::log!(target: TARGET, level, "{}:{}: {}", file, line, message);
+    }
+
+    #[cfg(feature = "defmt")]
+    {
+        let message = defmt::Display2Format(&message);
+        match level {
+            lfs::LFS_RUST_LOG_ERROR => defmt::error!("{}:{}: {}", file, line, message),
+            lfs::LFS_RUST_LOG_WARN => defmt::warn!("{}:{}: {}", file, line, message),
+            lfs::LFS_RUST_LOG_DEBUG => defmt::debug!("{}:{}: {}", file, line, message),
+            lfs::LFS_RUST_LOG_TRACE => defmt::trace!("{}:{}: {}", file, line, message),
+            _ => defmt::info!("{}:{}: {}", file, line, message),
+        }
+    }
+}
+
+/// Report a failed assertion of the C library. Panicking in an `extern "C"` function aborts.
+unsafe extern "C" fn assert_hook(
+    file: *const cty::c_char,
+    line: cty::c_int,
+    expr: *const cty::c_char,
+) {
+    panic!(
+        "littlefs assertion `{}` failed at {}:{}",
+        c_str(expr),
+        c_str(file),
+        line
+    );
+}
+
+#[cfg(test)]
+mod tests {
+
+    extern crate std;
+
+    use super::*;
+    use std::string::ToString;
+
+    fn format(fmt: &str, args: &[u32]) -> std::string::String {
+        Message::new(fmt.as_bytes(), args).to_string()
+    }
+
+    #[test]
+    fn test_message_format() {
+        assert_eq!(format("no conversions", &[]), "no conversions");
+        assert_eq!(
+            format("Corrupted dir pair at %u %u", &[2, 3]),
+            "Corrupted dir pair at 2 3"
+        );
+        assert_eq!(format("Bad block at %lu", &[7]), "Bad block at 7");
+        assert_eq!(format("%d%%", &[-1i32 as u32]), "-1%");
+        assert_eq!(format("0x%08x %X %c", &[0xbeef, 0xbeef, 'a' as u32]), "0xbeef BEEF a");
+        assert_eq!(format("%u and %u", &[1]), "1 and ?");
+        assert_eq!(format("trailing %", &[]), "trailing %");
+    }
+}
@@ -0,0 +1,364 @@
+//! File handles typed by the mode they were opened in.
+//!
+//! The access mode in `FileOpenFlags` is only checked by the C library at runtime, so writing to a
+//! file opened read only fails with an error far away from the mistake. The open functions below
+//! derive the access flags from the handle type they return instead, and each handle type only
+//! offers the operations allowed in its mode:
+//!
+//! | Handle            | Flags            | Operations                           |
+//! |-------------------|------------------|--------------------------------------|
+//! | [`ReadFile`]      | `RDONLY`         | read, seek                           |
+//! | [`WriteFile`]     | `WRONLY`         | write, truncate, sync, seek          |
+//! | [`ReadWriteFile`] | `RDWR`           | read, write, truncate, sync, seek    |
+//! | [`AppendFile`]    | `WRONLY, APPEND` | write, truncate, sync                |
+//!
+//! All handles report their position and size and are closed with `close`. A handle borrows the
+//! `File` it was opened on, which keeps the `File` in place while it is open. A handle dropped
+//! without being closed leaves the file open, so it must still be closed through
+//! `LittleFs::file_close`.
+//!
+//! A handle must be used with the filesystem it was opened on. Every operation first checks that
+//! its file is open on the filesystem passed in, and fails with `FsError::Badf` otherwise, so a
+//! handle passed to another filesystem of the same type cannot corrupt it.
+//!
+//! ```ignore
+//! let mut log = fs.open_append(&mut file, "/log.txt", FileOpenFlags::CREAT)?;
+//! log.write(&mut fs, b"boot\n")?;
+//! log.close(&mut fs)?;
+//! ```
+
+use core::marker::PhantomData;
+
+use crate::{
+    File, FileOpenFlags, FsError, LittleFs, Operation, Path, Storage, Whence, DEFAULT_PROG_SIZE,
+};
+
+mod sealed {
+    pub trait Sealed {}
+}
+
+/// Access mode of a file handle.
+pub trait Mode: sealed::Sealed {
+    /// Flags opening a file in this mode.
+    const FLAGS: FileOpenFlags;
+}
+
+/// Modes allowing data to be read.
+pub trait Readable: Mode {}
+
+/// Modes allowing data to be written.
+pub trait Writable: Mode {}
+
+/// Modes in which the position of reads and writes can be chosen.
+pub trait Seekable: Mode {}
+
+/// Read only access.
+pub enum Read {}
+
+/// Write only access.
+pub enum Write {}
+
+/// Read and write access.
+pub enum ReadWrite {}
+
+/// Write only access, with every write going to the end of the file.
+pub enum Append {}
+
+impl sealed::Sealed for Read {}
+impl sealed::Sealed for Write {}
+impl sealed::Sealed for ReadWrite {}
+impl sealed::Sealed for Append {}
+
+impl Mode for Read {
+    const FLAGS: FileOpenFlags = FileOpenFlags::RDONLY;
+}
+
+impl Mode for Write {
+    const FLAGS: FileOpenFlags = FileOpenFlags::WRONLY;
+}
+
+impl Mode for ReadWrite {
+    const FLAGS: FileOpenFlags = FileOpenFlags::RDWR;
+}
+
+impl Mode for Append {
+    const FLAGS: FileOpenFlags = FileOpenFlags::from_bits_truncate(
+        FileOpenFlags::WRONLY.bits() | FileOpenFlags::APPEND.bits(),
+    );
+}
+
+impl Readable for Read {}
+impl Readable for ReadWrite {}
+impl Writable for Write {}
+impl Writable for ReadWrite {}
+impl Writable for Append {}
+impl Seekable for Read {}
+impl Seekable for Write {}
+impl Seekable for ReadWrite {}
+
+/// Handle to a file open in mode `M`, borrowing the `File` it was opened on.
+pub struct OpenFile<'a, M: Mode, const CACHE_SIZE: usize> {
+    file: &'a mut File<CACHE_SIZE>,
+    mode: PhantomData<M>,
+}
+
+/// Handle to a file open for reading.
+pub type ReadFile<'a, const CACHE_SIZE: usize = DEFAULT_PROG_SIZE> = OpenFile<'a, Read, CACHE_SIZE>;
+
+/// Handle to a file open for writing.
+pub type WriteFile<'a, const CACHE_SIZE: usize = DEFAULT_PROG_SIZE> =
+    OpenFile<'a, Write, CACHE_SIZE>;
+
+/// Handle to a file open for reading and writing.
+pub type ReadWriteFile<'a, const CACHE_SIZE: usize = DEFAULT_PROG_SIZE> =
+    OpenFile<'a, ReadWrite, CACHE_SIZE>;
+
+/// Handle to a file open for appending.
+pub type AppendFile<'a, const CACHE_SIZE: usize = DEFAULT_PROG_SIZE> =
+    OpenFile<'a, Append, CACHE_SIZE>;
+
+/// Opening of typed file handles.
+impl<T: Storage, const READ_SIZE: usize, const PROG_SIZE: usize, const LOOKAHEAD_SIZE: usize>
+    LittleFs<T, READ_SIZE, PROG_SIZE, LOOKAHEAD_SIZE>
+{
+    /// Open an existing file for reading.
+    pub fn open_read<'a, P: AsRef<Path>>(
+        &mut self,
+        file: &'a mut File<PROG_SIZE>,
+        path: P,
+    ) -> Result<ReadFile<'a, PROG_SIZE>, FsError> {
+        self.open_mode(file, path.as_ref(), FileOpenFlags::empty())
+    }
+
+    /// Open a file for writing. `flags` may add `CREAT`, `EXCL` and `TRUNC`; its access flags
+    /// are ignored.
+    pub fn open_write<'a, P: AsRef<Path>>(
+        &mut self,
+        file: &'a mut File<PROG_SIZE>,
+        path: P,
+        flags: FileOpenFlags,
+    ) -> Result<WriteFile<'a, PROG_SIZE>, FsError> {
+        self.open_mode(file, path.as_ref(), flags)
+    }
+
+    /// Open a file for reading and writing. `flags` may add `CREAT`, `EXCL` and `TRUNC`; its
+    /// access flags are ignored.
+    pub fn open_read_write<'a, P: AsRef<Path>>(
+        &mut self,
+        file: &'a mut File<PROG_SIZE>,
+        path: P,
+        flags: FileOpenFlags,
+    ) -> Result<ReadWriteFile<'a, PROG_SIZE>, FsError> {
+        self.open_mode(file, path.as_ref(), flags)
+    }
+
+    /// Open a file for appending. `flags` may add `CREAT`, `EXCL` and `TRUNC`; its access flags
+    /// are ignored.
+    pub fn open_append<'a, P: AsRef<Path>>(
+        &mut self,
+        file: &'a mut File<PROG_SIZE>,
+        path: P,
+        flags: FileOpenFlags,
+    ) -> Result<AppendFile<'a, PROG_SIZE>, FsError> {
+        self.open_mode(file, path.as_ref(), flags)
+    }
+
+    /// Open a file in mode `M`, replacing the access flags of `flags` by those of the mode.
+    fn open_mode<'a, M: Mode>(
+        &mut self,
+        file: &'a mut File<PROG_SIZE>,
+        path: &Path,
+        flags: FileOpenFlags,
+    ) -> Result<OpenFile<'a, M, PROG_SIZE>, FsError> {
+        let flags = (flags - FileOpenFlags::RDWR - FileOpenFlags::APPEND) | M::FLAGS;
+        self.file_open(file, path, flags)?;
+        Ok(OpenFile {
+            file,
+            mode: PhantomData,
+        })
+    }
+}
+
+impl<'a, M: Mode, const CACHE_SIZE: usize> OpenFile<'a, M, CACHE_SIZE> {
+    /// Close the file. The handle is consumed even if `fs` is not the filesystem the file was
+    /// opened on, in which case the file stays open there.
+    pub fn close<T: Storage, const READ_SIZE: usize, const LOOKAHEAD_SIZE: usize>(
+        self,
+        fs: &mut LittleFs<T, READ_SIZE, CACHE_SIZE, LOOKAHEAD_SIZE>,
+    ) -> Result<(), FsError> {
+        fs.check_open(self.file, Operation::FileClose)?;
+        fs.file_close(self.file)
+    }
+
+    /// Tell current position of handle within the file.
+    pub fn tell<T: Storage, const READ_SIZE: usize, const LOOKAHEAD_SIZE: usize>(
+        &mut self,
+        fs: &mut LittleFs<T, READ_SIZE, CACHE_SIZE, LOOKAHEAD_SIZE>,
+    ) -> Result<usize, FsError> {
+        fs.check_open(self.file, Operation::FileTell)?;
+        fs.file_tell(self.file)
+    }
+
+    /// Return total number of bytes in file.
+    pub fn size<T: Storage, const READ_SIZE: usize, const LOOKAHEAD_SIZE: usize>(
+        &mut self,
+        fs: &mut LittleFs<T, READ_SIZE, CACHE_SIZE, LOOKAHEAD_SIZE>,
+    ) -> Result<usize, FsError> {
+        fs.check_open(self.file, Operation::FileSize)?;
+        fs.file_size(self.file)
+    }
+}
+
+impl<'a, M: Readable, const CACHE_SIZE: usize> OpenFile<'a, M, CACHE_SIZE> {
+    /// Read data from file.
+    pub fn read<T: Storage, const READ_SIZE: usize, const LOOKAHEAD_SIZE: usize>(
+        &mut self,
+        fs: &mut LittleFs<T, READ_SIZE, CACHE_SIZE, LOOKAHEAD_SIZE>,
+        buf: &mut [u8],
+    ) -> Result<usize, FsError> {
+        fs.check_open(self.file, Operation::FileRead)?;
+        fs.file_read(self.file, buf)
+    }
+}
+
+impl<'a, M: Writable, const CACHE_SIZE: usize> OpenFile<'a, M, CACHE_SIZE> {
+    /// Write data to file.
+    pub fn write<T: Storage, const READ_SIZE: usize, const LOOKAHEAD_SIZE: usize>(
+        &mut self,
+        fs: &mut LittleFs<T, READ_SIZE, CACHE_SIZE, LOOKAHEAD_SIZE>,
+        buf: &[u8],
+    ) -> Result<usize, FsError> {
+        fs.check_open(self.file, Operation::FileWrite)?;
+        fs.file_write(self.file, buf)
+    }
+
+    /// Truncate or extend the file to the given size.
+    pub fn truncate<T: Storage, const READ_SIZE: usize, const LOOKAHEAD_SIZE: usize>(
+        &mut self,
+        fs: &mut LittleFs<T, READ_SIZE, CACHE_SIZE, LOOKAHEAD_SIZE>,
+        size: usize,
+    ) -> Result<(), FsError> {
+        fs.check_open(self.file, Operation::FileTruncate)?;
+        fs.file_truncate(self.file, size)
+    }
+
+    /// Synchronize file contents to storage.
+    pub fn sync<T: Storage, const READ_SIZE: usize, const LOOKAHEAD_SIZE: usize>(
+        &mut self,
+        fs: &mut LittleFs<T, READ_SIZE, CACHE_SIZE, LOOKAHEAD_SIZE>,
+    ) -> Result<(), FsError> {
+        fs.check_open(self.file, Operation::FileSync)?;
+        fs.file_sync(self.file)
+    }
+}
+
+impl<'a, M: Seekable, const CACHE_SIZE: usize> OpenFile<'a, M, CACHE_SIZE> {
+    /// Change position of subsequent read / write in file.
+    pub fn seek<T: Storage, const READ_SIZE: usize, const LOOKAHEAD_SIZE: usize>(
+        &mut self,
+        fs: &mut LittleFs<T, READ_SIZE, CACHE_SIZE, LOOKAHEAD_SIZE>,
+        off: isize,
+        whence: Whence,
+    ) -> Result<usize, FsError> {
+        fs.check_open(self.file, Operation::FileSeek)?;
+        fs.file_seek(self.file, off, whence)
+    }
+
+    /// Rewind file handle to the start of the file.
+    pub fn rewind<T: Storage, const READ_SIZE: usize, const LOOKAHEAD_SIZE: usize>(
+        &mut self,
+        fs: &mut LittleFs<T, READ_SIZE, CACHE_SIZE, LOOKAHEAD_SIZE>,
+    ) -> Result<(), FsError> {
+        fs.check_open(self.file, Operation::FileRewind)?;
+        fs.file_rewind(self.file)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use crate::tests::RamStorage;
+
+    #[test]
+    fn test_typed_handles() {
+        let mut fs = LittleFs::new(RamStorage::default());
+        fs.format().unwrap();
+        let mut fs = fs.mount().unwrap();
+        let mut file = File::default();
+
+        assert_eq!(
+            fs.open_read(&mut file, "/log.txt").err(),
+            Some(FsError::Noent)
+        );
+
+        // The access flags passed along are replaced by those of the handle type.
+        let mut log = fs
+            .open_write(
+                &mut file,
+                "/log.txt",
+                FileOpenFlags::RDONLY | FileOpenFlags::CREAT,
+            )
+            .unwrap();
+        log.write(&mut fs, b"first\n").unwrap();
+        log.seek(&mut fs, 0, Whence::Set).unwrap();
+        log.write(&mut fs, b"FIRST\n").unwrap();
+        log.close(&mut fs).unwrap();
+
+        let mut log = fs
+            .open_append(&mut file, "/log.txt", FileOpenFlags::empty())
+            .unwrap();
+        log.write(&mut fs, b"second\n").unwrap();
+        assert_eq!(log.size(&mut fs).unwrap(), 13);
+        log.close(&mut fs).unwrap();
+
+        let mut log = fs.open_read(&mut file, "/log.txt").unwrap();
+        let mut buf = [0u8; 32];
+        let sz = log.read(&mut fs, &mut buf).unwrap();
+        assert_eq!(&buf[..sz], b"FIRST\nsecond\n");
+        log.seek(&mut fs, 6, Whence::Set).unwrap();
+        assert_eq!(log.tell(&mut fs).unwrap(), 6);
+        log.close(&mut fs).unwrap();
+
+        let mut log = fs
+            .open_read_write(&mut file, "/log.txt", FileOpenFlags::empty())
+            .unwrap();
+        log.truncate(&mut fs, 5).unwrap();
+        log.rewind(&mut fs).unwrap();
+        let sz = log.read(&mut fs, &mut buf).unwrap();
+        assert_eq!(&buf[..sz], b"FIRST");
+        log.close(&mut fs).unwrap();
+        fs.unmount().unwrap();
+    }
+
+    #[test]
+    fn test_handle_on_other_fs() {
+        let mut fs = LittleFs::new(RamStorage::default());
+        fs.format().unwrap();
+        let mut fs = fs.mount().unwrap();
+        let mut other = LittleFs::new(RamStorage::default());
+        other.format().unwrap();
+        let mut other = other.mount().unwrap();
+        let mut file = File::default();
+
+        let mut log = fs
+            .open_write(&mut file, "/log.txt", FileOpenFlags::CREAT)
+            .unwrap();
+        assert_eq!(log.write(&mut other, b"lost"), Err(FsError::Badf));
+        assert_eq!(
+            other.last_error().unwrap().operation(),
+            Operation::FileWrite
+        );
+        assert_eq!(log.sync(&mut other), Err(FsError::Badf));
+        log.write(&mut fs, b"kept").unwrap();
+        log.close(&mut fs).unwrap();
+
+        let mut log = fs.open_read(&mut file, "/log.txt").unwrap();
+        let mut buf = [0u8; 8];
+        assert_eq!(log.read(&mut other, &mut buf), Err(FsError::Badf));
+        assert_eq!(log.size(&mut fs), Ok(4));
+        log.close(&mut fs).unwrap();
+        other.unmount().unwrap();
+        fs.unmount().unwrap();
+    }
+}
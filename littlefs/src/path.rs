@@ -0,0 +1,592 @@
+//! Paths within the filesystem.
+//!
+//! [`Path`] is a borrowed path, a thin wrapper around `str` in the manner of `std::path::Path`,
+//! and [`PathBuf`] an owned path stored inline in a buffer of `N` bytes so that no allocator is
+//! needed. Components are separated by `/`; a leading `/` makes a path absolute. Every
+//! `LittleFs` function taking a path accepts anything implementing `AsRef<Path>`, including
+//! `&str`, `&Path` and `&PathBuf`.
+//!
+//! ```ignore
+//! let dir: PathBuf = Path::new("/data").join("logs")?;
+//! fs.mkdir(&dir)?;
+//! let file = dir.join("0.txt")?;
+//! assert_eq!(file.extension(), Some("txt"));
+//! ```
+
+use core::convert::TryFrom;
+use core::{cmp, fmt, hash, ops};
+
+use crate::{FsError, NAME_MAX_LEN};
+
+/// Separator between the components of a path.
+pub const SEPARATOR: char = '/';
+
+/// Borrowed path. Paths are not required to be normalized; see [`Path::normalize`].
+#[repr(transparent)]
+pub struct Path(str);
+
+/// Part of a path, as produced by [`Path::components`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Component<'a> {
+    /// The leading `/` of an absolute path.
+    RootDir,
+    /// A `.` referring to the current directory.
+    CurDir,
+    /// A `..` referring to the parent directory.
+    ParentDir,
+    /// A file or directory name.
+    Normal(&'a str),
+}
+
+impl<'a> Component<'a> {
+    /// The component as it appears in a path.
+    pub fn as_str(&self) -> &'a str {
+        match *self {
+            Component::RootDir => "/",
+            Component::CurDir => ".",
+            Component::ParentDir => "..",
+            Component::Normal(name) => name,
+        }
+    }
+}
+
+/// Iterator over the components of a path. Empty components from repeated or trailing slashes
+/// are skipped.
+#[derive(Clone)]
+pub struct Components<'a> {
+    rest: &'a str,
+    root: bool,
+}
+
+impl<'a> Iterator for Components<'a> {
+    type Item = Component<'a>;
+
+    fn next(&mut self) -> Option<Component<'a>> {
+        if self.root {
+            self.root = false;
+            return Some(Component::RootDir);
+        }
+        let rest = self.rest.trim_start_matches(SEPARATOR);
+        if rest.is_empty() {
+            self.rest = rest;
+            return None;
+        }
+        let end = rest.find(SEPARATOR).unwrap_or(rest.len());
+        let (name, rest) = rest.split_at(end);
+        self.rest = rest;
+        Some(match name {
+            "." => Component::CurDir,
+            ".." => Component::ParentDir,
+            _ => Component::Normal(name),
+        })
+    }
+}
+
+impl Path {
+    /// Borrow a string as a path.
+    pub fn new(path: &str) -> &Path {
+        // Path is a transparent wrapper around str.
+        unsafe { &*(path as *const str as *const Path) }
+    }
+
+    /// The path as a string.
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+
+    /// Whether the path starts at the root directory.
+    pub fn is_absolute(&self) -> bool {
+        self.0.starts_with(SEPARATOR)
+    }
+
+    /// Whether `base` is a prefix of the path, comparing whole components.
+    pub fn starts_with<P: AsRef<Path>>(&self, base: P) -> bool {
+        let mut components = self.components();
+        base.as_ref()
+            .components()
+            .all(|component| components.next() == Some(component))
+    }
+
+    /// Iterate over the components of the path.
+    pub fn components(&self) -> Components<'_> {
+        Components {
+            rest: &self.0,
+            root: self.is_absolute(),
+        }
+    }
+
+    /// The path without its final component, or `None` if the path is the root directory or
+    /// empty. The parent of a single relative name is the empty path.
+    pub fn parent(&self) -> Option<&Path> {
+        let path = self.0.trim_end_matches(SEPARATOR);
+        if path.is_empty() {
+            return None;
+        }
+        let parent = match path.rfind(SEPARATOR) {
+            Some(end) => path[..end].trim_end_matches(SEPARATOR),
+            None => "",
+        };
+        if parent.is_empty() && self.is_absolute() {
+            Some(Path::new(&self.0[..1]))
+        } else {
+            Some(Path::new(parent))
+        }
+    }
+
+    /// The final component of the path if it is a name, rather than `.`, `..` or the root.
+    pub fn file_name(&self) -> Option<&str> {
+        match self.components().last()? {
+            Component::Normal(name) => Some(name),
+            _ => None,
+        }
+    }
+
+    /// The final name of the path without its extension.
+    pub fn file_stem(&self) -> Option<&str> {
+        let name = self.file_name()?;
+        Some(match split_extension(name) {
+            Some((stem, _)) => stem,
+            None => name,
+        })
+    }
+
+    /// The extension of the final name of the path: the part after its last `.`, unless the
+    /// name starts with its only `.`.
+    pub fn extension(&self) -> Option<&str> {
+        split_extension(self.file_name()?).map(|(_, extension)| extension)
+    }
+
+    /// Append `path` to this path. An absolute `path` replaces this path.
+    pub fn join<P: AsRef<Path>, const N: usize>(&self, path: P) -> Result<PathBuf<N>, FsError> {
+        let mut buf = PathBuf::try_from(self.as_str())?;
+        buf.push(path)?;
+        Ok(buf)
+    }
+
+    /// Resolve `.` and `..` components and remove repeated and trailing slashes. A `..` at the
+    /// root stays at the root, while leading `..` components of a relative path are kept. An
+    /// empty relative path normalizes to `.`.
+    pub fn normalize<const N: usize>(&self) -> Result<PathBuf<N>, FsError> {
+        let mut buf = PathBuf::new();
+        // Names pushed so far which a following `..` can remove.
+        let mut names = 0;
+        for component in self.components() {
+            match component {
+                Component::RootDir => buf.push_str("/")?,
+                Component::CurDir => (),
+                Component::ParentDir if names > 0 => {
+                    buf.pop();
+                    names -= 1;
+                }
+                Component::ParentDir if !buf.is_absolute() => {
+                    buf.push(Path::new(".."))?;
+                }
+                Component::ParentDir => (),
+                Component::Normal(name) => {
+                    buf.push(Path::new(name))?;
+                    names += 1;
+                }
+            }
+        }
+        if buf.as_str().is_empty() {
+            buf.push_str(".")?;
+        }
+        Ok(buf)
+    }
+
+    /// Copy the path into a null terminated buffer for the C library. Paths longer than
+    /// littlefs accepts or containing a null byte are rejected.
+    pub(crate) fn to_c_path(&self) -> Result<[u8; NAME_MAX_LEN + 1], FsError> {
+        let bytes = self.0.as_bytes();
+        if bytes.len() > NAME_MAX_LEN || bytes.contains(&0) {
+            return Err(FsError::Inval);
+        }
+        let mut cstr = [0u8; NAME_MAX_LEN + 1];
+        cstr[..bytes.len()].copy_from_slice(bytes);
+        Ok(cstr)
+    }
+}
+
+/// Split a name into its stem and extension.
+fn split_extension(name: &str) -> Option<(&str, &str)> {
+    match name.rfind('.') {
+        Some(0) | None => None,
+        Some(dot) => Some((&name[..dot], &name[dot + 1..])),
+    }
+}
+
+impl AsRef<Path> for Path {
+    fn as_ref(&self) -> &Path {
+        self
+    }
+}
+
+impl AsRef<Path> for str {
+    fn as_ref(&self) -> &Path {
+        Path::new(self)
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl AsRef<Path> for alloc::string::String {
+    fn as_ref(&self) -> &Path {
+        Path::new(self)
+    }
+}
+
+#[cfg(all(feature = "std", not(feature = "alloc")))]
+impl AsRef<Path> for std::string::String {
+    fn as_ref(&self) -> &Path {
+        Path::new(self)
+    }
+}
+
+impl AsRef<str> for Path {
+    fn as_ref(&self) -> &str {
+        &self.0
+    }
+}
+
+impl fmt::Debug for Path {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::Debug::fmt(&self.0, f)
+    }
+}
+
+impl fmt::Display for Path {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl PartialEq for Path {
+    fn eq(&self, other: &Path) -> bool {
+        self.0 == other.0
+    }
+}
+
+impl Eq for Path {}
+
+impl PartialEq<str> for Path {
+    fn eq(&self, other: &str) -> bool {
+        &self.0 == other
+    }
+}
+
+impl<'a> PartialEq<&'a str> for Path {
+    fn eq(&self, other: &&'a str) -> bool {
+        &self.0 == *other
+    }
+}
+
+impl PartialOrd for Path {
+    fn partial_cmp(&self, other: &Path) -> Option<cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Path {
+    fn cmp(&self, other: &Path) -> cmp::Ordering {
+        self.0.cmp(&other.0)
+    }
+}
+
+impl hash::Hash for Path {
+    fn hash<H: hash::Hasher>(&self, state: &mut H) {
+        self.0.hash(state);
+    }
+}
+
+/// Owned path stored in a buffer of `N` bytes. Operations that would exceed the capacity fail
+/// with `FsError::Inval` and leave the path unchanged. The default capacity is the longest path
+/// littlefs accepts.
+#[derive(Clone)]
+pub struct PathBuf<const N: usize = NAME_MAX_LEN> {
+    buf: [u8; N],
+    len: usize,
+}
+
+impl<const N: usize> PathBuf<N> {
+    /// Create an empty path.
+    pub fn new() -> Self {
+        PathBuf {
+            buf: [0; N],
+            len: 0,
+        }
+    }
+
+    /// Number of bytes the path can hold.
+    pub fn capacity(&self) -> usize {
+        N
+    }
+
+    /// Borrow the path.
+    pub fn as_path(&self) -> &Path {
+        // Only whole strings are ever copied into the buffer.
+        Path::new(unsafe { core::str::from_utf8_unchecked(&self.buf[..self.len]) })
+    }
+
+    /// Append `path`, inserting a separator if needed. An absolute `path` replaces the current
+    /// contents.
+    pub fn push<P: AsRef<Path>>(&mut self, path: P) -> Result<(), FsError> {
+        let path = path.as_ref().as_str();
+        if path.starts_with(SEPARATOR) {
+            if path.len() > N {
+                return Err(FsError::Inval);
+            }
+            self.len = 0;
+            return self.push_str(path);
+        }
+        let separator = !self.as_str().is_empty() && !self.as_str().ends_with(SEPARATOR);
+        if self.len + separator as usize + path.len() > N {
+            return Err(FsError::Inval);
+        }
+        if separator {
+            self.push_str("/")?;
+        }
+        self.push_str(path)
+    }
+
+    /// Remove the final component, returning whether there was one.
+    pub fn pop(&mut self) -> bool {
+        match self.as_path().parent() {
+            Some(parent) => {
+                self.len = parent.as_str().len();
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Remove all components.
+    pub fn clear(&mut self) {
+        self.len = 0;
+    }
+
+    /// Append a string without inserting a separator.
+    pub(crate) fn push_str(&mut self, s: &str) -> Result<(), FsError> {
+        let end = self.len + s.len();
+        if end > N {
+            return Err(FsError::Inval);
+        }
+        self.buf[self.len..end].copy_from_slice(s.as_bytes());
+        self.len = end;
+        Ok(())
+    }
+}
+
+impl<const N: usize> Default for PathBuf<N> {
+    fn default() -> Self {
+        PathBuf::new()
+    }
+}
+
+impl<'a, const N: usize> TryFrom<&'a str> for PathBuf<N> {
+    type Error = FsError;
+
+    /// Copy a path, failing with `Inval` if it does not fit.
+    fn try_from(path: &'a str) -> Result<Self, FsError> {
+        let mut buf = PathBuf::new();
+        buf.push_str(path)?;
+        Ok(buf)
+    }
+}
+
+impl<'a, const N: usize> TryFrom<&'a Path> for PathBuf<N> {
+    type Error = FsError;
+
+    /// Copy a path, failing with `Inval` if it does not fit.
+    fn try_from(path: &'a Path) -> Result<Self, FsError> {
+        PathBuf::try_from(path.as_str())
+    }
+}
+
+impl<const N: usize> ops::Deref for PathBuf<N> {
+    type Target = Path;
+
+    fn deref(&self) -> &Path {
+        self.as_path()
+    }
+}
+
+impl<const N: usize> AsRef<Path> for PathBuf<N> {
+    fn as_ref(&self) -> &Path {
+        self.as_path()
+    }
+}
+
+impl<const N: usize> fmt::Debug for PathBuf<N> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::Debug::fmt(self.as_path(), f)
+    }
+}
+
+impl<const N: usize> fmt::Display for PathBuf<N> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::Display::fmt(self.as_path(), f)
+    }
+}
+
+impl<const N: usize, const M: usize> PartialEq<PathBuf<M>> for PathBuf<N> {
+    fn eq(&self, other: &PathBuf<M>) -> bool {
+        self.as_path() == other.as_path()
+    }
+}
+
+impl<const N: usize> Eq for PathBuf<N> {}
+
+impl<const N: usize> PartialEq<str> for PathBuf<N> {
+    fn eq(&self, other: &str) -> bool {
+        self.as_str() == other
+    }
+}
+
+impl<'a, const N: usize> PartialEq<&'a str> for PathBuf<N> {
+    fn eq(&self, other: &&'a str) -> bool {
+        self.as_str() == *other
+    }
+}
+
+impl<const N: usize> PartialOrd for PathBuf<N> {
+    fn partial_cmp(&self, other: &PathBuf<N>) -> Option<cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl<const N: usize> Ord for PathBuf<N> {
+    fn cmp(&self, other: &PathBuf<N>) -> cmp::Ordering {
+        self.as_path().cmp(other.as_path())
+    }
+}
+
+impl<const N: usize> hash::Hash for PathBuf<N> {
+    fn hash<H: hash::Hasher>(&self, state: &mut H) {
+        self.as_path().hash(state);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use crate::tests::RamStorage;
+    use crate::{Dir, EntryType, File, FileOpenFlags, Info, LittleFs};
+
+    fn normalize(path: &str) -> PathBuf<64> {
+        Path::new(path).normalize().unwrap()
+    }
+
+    #[test]
+    fn test_components() {
+        let mut components = Path::new("//etc/./net/../hosts/").components();
+        assert_eq!(components.next(), Some(Component::RootDir));
+        assert_eq!(components.next(), Some(Component::Normal("etc")));
+        assert_eq!(components.next(), Some(Component::CurDir));
+        assert_eq!(components.next(), Some(Component::Normal("net")));
+        assert_eq!(components.next(), Some(Component::ParentDir));
+        assert_eq!(components.next(), Some(Component::Normal("hosts")));
+        assert_eq!(components.next(), None);
+        assert_eq!(Path::new("").components().next(), None);
+    }
+
+    #[test]
+    fn test_parent_and_names() {
+        assert_eq!(
+            Path::new("/etc/net/hosts").parent(),
+            Some(Path::new("/etc/net"))
+        );
+        assert_eq!(Path::new("/etc//hosts/").parent(), Some(Path::new("/etc")));
+        assert_eq!(Path::new("/etc").parent(), Some(Path::new("/")));
+        assert_eq!(Path::new("etc").parent(), Some(Path::new("")));
+        assert_eq!(Path::new("/").parent(), None);
+        assert_eq!(Path::new("").parent(), None);
+
+        let path = Path::new("/log/boot.2.txt");
+        assert_eq!(path.file_name(), Some("boot.2.txt"));
+        assert_eq!(path.file_stem(), Some("boot.2"));
+        assert_eq!(path.extension(), Some("txt"));
+        assert_eq!(Path::new("/.config").extension(), None);
+        assert_eq!(Path::new("/.config").file_stem(), Some(".config"));
+        assert_eq!(Path::new("/log/..").file_name(), None);
+        assert_eq!(Path::new("/").file_name(), None);
+
+        assert!(Path::new("/log/boot.txt").starts_with("/log"));
+        assert!(Path::new("/log/boot.txt").starts_with("//log/"));
+        assert!(!Path::new("/logs").starts_with("/log"));
+        assert!(!Path::new("log").starts_with("/log"));
+    }
+
+    #[test]
+    fn test_join_and_push() {
+        let path: PathBuf<32> = Path::new("/etc").join("net").unwrap();
+        assert_eq!(path, "/etc/net");
+        let path: PathBuf<32> = Path::new("/").join("etc").unwrap();
+        assert_eq!(path, "/etc");
+        let path: PathBuf<32> = Path::new("/etc").join("/log").unwrap();
+        assert_eq!(path, "/log");
+
+        let mut path: PathBuf<8> = PathBuf::try_from("/etc").unwrap();
+        assert_eq!(path.push("hosts"), Err(FsError::Inval));
+        assert_eq!(path, "/etc");
+        path.push("net").unwrap();
+        assert_eq!(path, "/etc/net");
+        assert!(path.pop());
+        assert!(path.pop());
+        assert_eq!(path, "/");
+        assert!(!path.pop());
+        assert_eq!(PathBuf::<4>::try_from("/etc/net"), Err(FsError::Inval));
+    }
+
+    #[test]
+    fn test_normalize() {
+        assert_eq!(normalize("/etc/./net/../hosts"), "/etc/hosts");
+        assert_eq!(normalize("//etc///hosts/"), "/etc/hosts");
+        assert_eq!(normalize("/.."), "/");
+        assert_eq!(normalize("/etc/../.."), "/");
+        assert_eq!(normalize("../a/./b/.."), "../a");
+        assert_eq!(normalize("a/.."), ".");
+        assert_eq!(normalize(""), ".");
+        assert_eq!(
+            Path::new("/etc/hosts").normalize::<4>(),
+            Err(FsError::Inval)
+        );
+    }
+
+    #[test]
+    fn test_fs_paths() {
+        let mut lfs = LittleFs::new(RamStorage::default());
+        lfs.format().unwrap();
+        let mut lfs = lfs.mount().unwrap();
+
+        let dir: PathBuf = Path::new("/data").join("logs").unwrap();
+        lfs.mkdir(Path::new("/data")).unwrap();
+        lfs.mkdir(&dir).unwrap();
+        let file_path: PathBuf = dir.join("0.txt").unwrap();
+        let mut file = File::default();
+        lfs.file_open(
+            &mut file,
+            &file_path,
+            FileOpenFlags::WRONLY | FileOpenFlags::CREAT,
+        )
+        .unwrap();
+        lfs.file_close(&mut file).unwrap();
+
+        let renamed = Path::new("/data/./logs/../logs/1.txt")
+            .normalize::<64>()
+            .unwrap();
+        lfs.rename(&file_path, &renamed).unwrap();
+        let mut info = Info::default();
+        lfs.stat(&renamed, &mut info).unwrap();
+        assert_eq!(info.entry_type, EntryType::RegularFile);
+
+        let mut d = Dir::default();
+        lfs.dir_open(&mut d, &dir).unwrap();
+        lfs.dir_close(&mut d).unwrap();
+        lfs.remove(renamed).unwrap();
+
+        let mut long = PathBuf::<{ NAME_MAX_LEN + 8 }>::new();
+        while long.push("directory").is_ok() {}
+        assert_eq!(lfs.mkdir(&long), Err(FsError::Inval));
+        lfs.unmount().unwrap();
+    }
+}
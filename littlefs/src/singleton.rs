@@ -0,0 +1,272 @@
+//! Filesystem instances placed in static memory.
+//!
+//! littlefs keeps pointers to every open `File`, so file handles must not move while they are open.
+//! [`StaticLittleFs`] reserves memory for a filesystem and a fixed number of file handles in a
+//! `static` and hands out a single `&'static mut` reference to the mounted filesystem, as needed
+//! by RTIC resources or embassy tasks. The [`static_littlefs!`] macro
+//! declares the static and initializes it in one step.
+//!
+//! [`static_littlefs!`]: ../macro.static_littlefs.html
+
+use core::cell::UnsafeCell;
+use core::mem::MaybeUninit;
+use core::sync::atomic::{AtomicBool, Ordering};
+
+use crate::{File, FsError, LittleFs, Storage, Unmounted};
+use crate::{DEFAULT_LOOKAHEAD_SIZE, DEFAULT_PROG_SIZE, DEFAULT_READ_SIZE};
+
+/// Mounted filesystem together with its file handle slots.
+pub struct StaticMount<
+    T: Storage,
+    const FILES: usize,
+    const READ_SIZE: usize = DEFAULT_READ_SIZE,
+    const PROG_SIZE: usize = DEFAULT_PROG_SIZE,
+    const LOOKAHEAD_SIZE: usize = DEFAULT_LOOKAHEAD_SIZE,
+> {
+    /// The mounted filesystem.
+    pub fs: LittleFs<T, READ_SIZE, PROG_SIZE, LOOKAHEAD_SIZE>,
+    /// File handles with a stable address, to be opened on `fs`.
+    pub files: [File<PROG_SIZE>; FILES],
+}
+
+// The filesystem and every file handle it can point to live in the same static, and access is
+// exclusive through the single `&'static mut` handed out, so moving that reference to another
+// thread is sound whenever the storage itself may be moved.
+unsafe impl<
+        T: Storage + Send,
+        const FILES: usize,
+        const READ_SIZE: usize,
+        const PROG_SIZE: usize,
+        const LOOKAHEAD_SIZE: usize,
+    > Send for StaticMount<T, FILES, READ_SIZE, PROG_SIZE, LOOKAHEAD_SIZE>
+{
+}
+
+/// Static memory for a filesystem and `FILES` file handles, initialized at most once.
+pub struct StaticLittleFs<
+    T: Storage,
+    const FILES: usize,
+    const READ_SIZE: usize = DEFAULT_READ_SIZE,
+    const PROG_SIZE: usize = DEFAULT_PROG_SIZE,
+    const LOOKAHEAD_SIZE: usize = DEFAULT_LOOKAHEAD_SIZE,
+> {
+    taken: AtomicBool,
+    mount: UnsafeCell<MaybeUninit<StaticMount<T, FILES, READ_SIZE, PROG_SIZE, LOOKAHEAD_SIZE>>>,
+}
+
+// Only the first call to `init_with` gains access to the contents.
+unsafe impl<
+        T: Storage + Send,
+        const FILES: usize,
+        const READ_SIZE: usize,
+        const PROG_SIZE: usize,
+        const LOOKAHEAD_SIZE: usize,
+    > Sync for StaticLittleFs<T, FILES, READ_SIZE, PROG_SIZE, LOOKAHEAD_SIZE>
+{
+}
+
+impl<
+        T: Storage,
+        const FILES: usize,
+        const READ_SIZE: usize,
+        const PROG_SIZE: usize,
+        const LOOKAHEAD_SIZE: usize,
+    > Default for StaticLittleFs<T, FILES, READ_SIZE, PROG_SIZE, LOOKAHEAD_SIZE>
+{
+    fn default() -> Self {
+        StaticLittleFs::new()
+    }
+}
+
+impl<
+        T: Storage,
+        const FILES: usize,
+        const READ_SIZE: usize,
+        const PROG_SIZE: usize,
+        const LOOKAHEAD_SIZE: usize,
+    > StaticLittleFs<T, FILES, READ_SIZE, PROG_SIZE, LOOKAHEAD_SIZE>
+{
+    /// Create the uninitialized memory, suitable for a `static`.
+    pub const fn new() -> Self {
+        StaticLittleFs {
+            taken: AtomicBool::new(false),
+            mount: UnsafeCell::new(MaybeUninit::uninit()),
+        }
+    }
+
+    /// Place a filesystem on the given storage into static memory and mount it. The memory is
+    /// released again if mounting fails.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the memory was already initialized.
+    #[allow(clippy::mut_from_ref)]
+    pub fn init(
+        &'static self,
+        storage: T,
+    ) -> Result<&'static mut StaticMount<T, FILES, READ_SIZE, PROG_SIZE, LOOKAHEAD_SIZE>, FsError>
+    {
+        self.init_with(storage, |fs| fs.mount().map_err(FsError::from))
+    }
+
+    /// Create a filesystem on the given storage, mount it with the given function and place it
+    /// into static memory. The memory is released again if the function fails, so that
+    /// initialization can be retried.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the memory was already initialized or another initialization is in progress.
+    #[allow(clippy::mut_from_ref)]
+    pub fn init_with<F>(
+        &'static self,
+        storage: T,
+        prepare: F,
+    ) -> Result<&'static mut StaticMount<T, FILES, READ_SIZE, PROG_SIZE, LOOKAHEAD_SIZE>, FsError>
+    where
+        F: FnOnce(
+            LittleFs<T, READ_SIZE, PROG_SIZE, LOOKAHEAD_SIZE, Unmounted>,
+        ) -> Result<LittleFs<T, READ_SIZE, PROG_SIZE, LOOKAHEAD_SIZE>, FsError>,
+    {
+        if self.taken.swap(true, Ordering::AcqRel) {
+            panic!("static filesystem initialized twice");
+        }
+        let fs = match prepare(LittleFs::new_sized(storage)) {
+            Ok(fs) => fs,
+            Err(err) => {
+                self.taken.store(false, Ordering::Release);
+                return Err(err);
+            }
+        };
+        // The flag guarantees that no other reference to the contents exists.
+        let slot = unsafe { &mut *self.mount.get() };
+        Ok(slot.write(StaticMount {
+            fs,
+            files: core::array::from_fn(|_| File::default()),
+        }))
+    }
+}
+
+/// Declare a static filesystem with the given storage type and number of file handles, and mount
+/// it on the given storage. Evaluates to a
+/// `Result<&'static mut StaticMount<Storage, FILES>, FsError>`. The read, program and lookahead
+/// sizes may be given after the number of file handles, and otherwise take their defaults.
+///
+/// Each invocation owns its own static, so evaluating the same invocation again after it mounted
+/// successfully panics. A failed mount releases the static and may be retried.
+///
+/// ```ignore
+/// let mount = static_littlefs!(Flash, 4, flash)?;
+/// let (fs, files) = (&mut mount.fs, &mut mount.files);
+/// fs.file_open(&mut files[0], "/log.txt", FileOpenFlags::RDONLY)?;
+///
+/// let mount = static_littlefs!(Flash, 4, 64, 128, 8, flash)?;
+/// ```
+#[macro_export]
+macro_rules! static_littlefs {
+    ($storage_type:ty, $files:expr, $storage:expr) => {{
+        static FS: $crate::singleton::StaticLittleFs<$storage_type, $files> =
+            $crate::singleton::StaticLittleFs::new();
+        FS.init($storage)
+    }};
+    ($storage_type:ty, $files:expr, $read_size:expr, $prog_size:expr, $lookahead_size:expr,
+        $storage:expr) => {{
+        static FS: $crate::singleton::StaticLittleFs<
+            $storage_type,
+            $files,
+            $read_size,
+            $prog_size,
+            $lookahead_size,
+        > = $crate::singleton::StaticLittleFs::new();
+        FS.init($storage)
+    }};
+}
+
+#[cfg(test)]
+mod tests {
+
+    extern crate std;
+
+    use super::*;
+    use crate::tests::RamStorage;
+    use crate::FileOpenFlags;
+
+    #[test]
+    fn test_static_littlefs() {
+        static FS: StaticLittleFs<RamStorage, 2> = StaticLittleFs::new();
+        let mount = FS
+            .init_with(RamStorage::default(), |mut fs| {
+                fs.format()?;
+                Ok(fs.mount()?)
+            })
+            .unwrap();
+
+        let (fs, files) = (&mut mount.fs, &mut mount.files);
+        let (first, second) = files.split_at_mut(1);
+        fs.file_open(
+            &mut first[0],
+            "/a.txt",
+            FileOpenFlags::WRONLY | FileOpenFlags::CREAT,
+        )
+        .unwrap();
+        fs.file_open(
+            &mut second[0],
+            "/b.txt",
+            FileOpenFlags::WRONLY | FileOpenFlags::CREAT,
+        )
+        .unwrap();
+        fs.file_write(&mut first[0], b"first").unwrap();
+        fs.file_write(&mut second[0], b"second").unwrap();
+        fs.file_close(&mut first[0]).unwrap();
+        fs.file_close(&mut second[0]).unwrap();
+
+        fs.file_open(&mut files[0], "/b.txt", FileOpenFlags::RDONLY)
+            .unwrap();
+        let mut buf = [0u8; 16];
+        let sz = fs.file_read(&mut files[0], &mut buf).unwrap();
+        assert_eq!(&buf[..sz], b"second");
+        fs.file_close(&mut files[0]).unwrap();
+    }
+
+    fn formatted<const READ_SIZE: usize, const PROG_SIZE: usize, const LOOKAHEAD_SIZE: usize>(
+    ) -> RamStorage {
+        let mut fs: LittleFs<RamStorage, READ_SIZE, PROG_SIZE, LOOKAHEAD_SIZE, Unmounted> =
+            LittleFs::new_sized(RamStorage::default());
+        fs.format().unwrap();
+        fs.into_inner()
+    }
+
+    #[test]
+    fn test_static_littlefs_macro() {
+        fn mount(storage: RamStorage) -> Result<&'static mut StaticMount<RamStorage, 1>, FsError> {
+            static_littlefs!(RamStorage, 1, storage)
+        }
+
+        // A failed mount releases the static so that it can be retried.
+        assert_eq!(mount(RamStorage::default()).err(), Some(FsError::Corrupt));
+        let mount_ok = mount(formatted::<256, 256, 16>()).unwrap();
+        mount_ok.fs.mkdir("/data").unwrap();
+        let result = std::panic::catch_unwind(|| mount(formatted::<256, 256, 16>()).is_ok());
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_static_littlefs_macro_sized() {
+        fn mount(
+            storage: RamStorage,
+        ) -> Result<&'static mut StaticMount<RamStorage, 2, 64, 128, 8>, FsError> {
+            static_littlefs!(RamStorage, 2, 64, 128, 8, storage)
+        }
+
+        let mount = mount(formatted::<64, 128, 8>()).unwrap();
+        let (fs, files) = (&mut mount.fs, &mut mount.files);
+        assert_eq!(fs.config().read_size, 64);
+        fs.file_open(
+            &mut files[1],
+            "/a.txt",
+            FileOpenFlags::WRONLY | FileOpenFlags::CREAT,
+        )
+        .unwrap();
+        fs.file_write(&mut files[1], b"sized").unwrap();
+        fs.file_close(&mut files[1]).unwrap();
+    }
+}
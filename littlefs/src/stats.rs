@@ -0,0 +1,129 @@
+//! I/O accounting for filesystem operations.
+//!
+//! When enabled through [`LittleFs::enable_io_stats`] every read, program and erase issued by
+//! littlefs is recorded both for the most recent filesystem call and cumulatively, together with
+//! the number of bytes handed to `file_write`. This makes it possible to reason about write
+//! amplification of a given access pattern.
+//!
+//! [`LittleFs::enable_io_stats`]: ../struct.LittleFs.html#method.enable_io_stats
+
+/// Counters of the storage operations performed by the filesystem.
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct IoStats {
+    /// Number of read operations issued to storage.
+    pub reads: u32,
+    /// Number of bytes read from storage.
+    pub bytes_read: u64,
+    /// Number of program operations issued to storage.
+    pub programs: u32,
+    /// Number of bytes programmed to storage.
+    pub bytes_programmed: u64,
+    /// Number of blocks erased.
+    pub erases: u32,
+    /// Number of bytes erased.
+    pub bytes_erased: u64,
+    /// Number of bytes of file data written by the caller through `file_write`.
+    pub bytes_written: u64,
+}
+
+impl IoStats {
+    /// Ratio of bytes programmed to storage to file data bytes written by the caller. Returns
+    /// `None` if no file data was written.
+    pub fn write_amplification(&self) -> Option<f32> {
+        if self.bytes_written == 0 {
+            return None;
+        }
+        Some(self.bytes_programmed as f32 / self.bytes_written as f32)
+    }
+
+    /// Number of block erases per KiB of file data written by the caller. Returns `None` if no
+    /// file data was written.
+    pub fn erases_per_kib(&self) -> Option<f32> {
+        if self.bytes_written == 0 {
+            return None;
+        }
+        Some(self.erases as f32 * 1024.0 / self.bytes_written as f32)
+    }
+
+    pub(crate) fn record_read(&mut self, size: usize) {
+        self.reads += 1;
+        self.bytes_read += size as u64;
+    }
+
+    pub(crate) fn record_prog(&mut self, size: usize) {
+        self.programs += 1;
+        self.bytes_programmed += size as u64;
+    }
+
+    pub(crate) fn record_erase(&mut self, size: usize) {
+        self.erases += 1;
+        self.bytes_erased += size as u64;
+    }
+
+    pub(crate) fn record_write(&mut self, size: usize) {
+        self.bytes_written += size as u64;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use crate::tests::RamStorage;
+    use crate::{FileOpenFlags, LittleFs};
+
+    #[test]
+    fn test_io_stats_disabled() {
+        let storage = RamStorage::default();
+        let mut lfs = LittleFs::new(storage);
+        lfs.format().unwrap();
+        let mut lfs = lfs.mount().unwrap();
+        lfs.mkdir("/foo").unwrap();
+        assert!(lfs.io_stats().is_none());
+        assert!(lfs.last_io_stats().is_none());
+        lfs.unmount().unwrap();
+    }
+
+    #[test]
+    fn test_io_stats_per_call() {
+        let storage = RamStorage::default();
+        let mut lfs = LittleFs::new(storage);
+        lfs.format().unwrap();
+        let mut lfs = lfs.mount().unwrap();
+        lfs.enable_io_stats();
+
+        lfs.mkdir("/foo").unwrap();
+        let mkdir_stats = lfs.last_io_stats().unwrap();
+        assert_ne!(mkdir_stats.programs, 0);
+        assert_ne!(mkdir_stats.erases, 0);
+        assert_eq!(mkdir_stats.bytes_written, 0);
+        assert!(mkdir_stats.write_amplification().is_none());
+
+        let mut file = Default::default();
+        lfs.file_open(
+            &mut file,
+            "/foo/bar.txt",
+            FileOpenFlags::WRONLY | FileOpenFlags::CREAT,
+        )
+        .unwrap();
+        let sz = lfs.file_write(&mut file, b"hello world!").unwrap();
+        assert_eq!(lfs.last_io_stats().unwrap().bytes_written, sz as u64);
+        lfs.file_close(&mut file).unwrap();
+        let close_stats = lfs.last_io_stats().unwrap();
+        assert_ne!(close_stats.programs, 0);
+        assert_eq!(close_stats.bytes_written, 0);
+
+        let total = lfs.io_stats().unwrap();
+        assert_eq!(total.bytes_written, sz as u64);
+        assert!(total.programs >= mkdir_stats.programs + close_stats.programs);
+        assert!(total.write_amplification().unwrap() > 1.0);
+        assert!(total.erases_per_kib().unwrap() > 0.0);
+
+        lfs.reset_io_stats();
+        assert_eq!(lfs.io_stats(), Some(IoStats::default()));
+        lfs.disable_io_stats();
+        assert!(lfs.io_stats().is_none());
+
+        lfs.unmount().unwrap();
+    }
+}
@@ -0,0 +1,226 @@
+//! Recursive creation and removal of directories.
+//!
+//! `mkdir` requires the parent directory to exist and `remove` refuses directories that are not
+//! empty. [`LittleFs::create_dir_all`] and [`LittleFs::remove_dir_all`] walk the tree on top of
+//! them using a single path buffer and directory handle, so their memory use does not depend on
+//! the depth of the tree. On failure `last_error` names the call and the entry that failed:
+//!
+//! ```ignore
+//! if fs.remove_dir_all("/data").is_err() {
+//!     // "remove `/data/logs/0.txt`: input/output error"
+//!     log::error!("{}", fs.last_error().unwrap());
+//! }
+//! ```
+//!
+//! [`LittleFs::create_dir_all`]: ../struct.LittleFs.html#method.create_dir_all
+//! [`LittleFs::remove_dir_all`]: ../struct.LittleFs.html#method.remove_dir_all
+
+use core::convert::TryFrom;
+
+use crate::path::{Component, Path, PathBuf};
+use crate::{Dir, EntryType, FsError, Info, LittleFs, Operation, Storage};
+
+impl<T: Storage, const READ_SIZE: usize, const PROG_SIZE: usize, const LOOKAHEAD_SIZE: usize>
+    LittleFs<T, READ_SIZE, PROG_SIZE, LOOKAHEAD_SIZE>
+{
+    /// Create a directory and any missing parents. Directories that already exist are left
+    /// alone; an existing entry on the path that is not a directory fails with `NotDir`.
+    pub fn create_dir_all<P: AsRef<Path>>(&mut self, path: P) -> Result<(), FsError> {
+        let mut buf = PathBuf::new();
+        let res = self.create_dir_all_in(path.as_ref(), &mut buf);
+        self.finish_composite(Operation::CreateDirAll, buf.as_str(), res)
+    }
+
+    /// Remove a directory after removing everything below it. The path is normalized first, and
+    /// the root directory cannot be removed, so it is rejected with `Inval` before anything is
+    /// touched.
+    pub fn remove_dir_all<P: AsRef<Path>>(&mut self, path: P) -> Result<(), FsError> {
+        let path = path.as_ref();
+        let mut buf = PathBuf::new();
+        let res = PathBuf::try_from(path).and_then(|path| {
+            buf = path;
+            buf = buf.normalize()?;
+            if !buf
+                .components()
+                .any(|component| matches!(component, Component::Normal(_)))
+            {
+                return Err(FsError::Inval);
+            }
+            self.remove_dir_all_in(&mut buf)
+        });
+        self.finish_composite(Operation::RemoveDirAll, buf.as_str(), res)
+    }
+
+    fn create_dir_all_in(&mut self, path: &Path, buf: &mut PathBuf) -> Result<(), FsError> {
+        let path: PathBuf = path.normalize()?;
+        let mut info = Info::default();
+        for component in path.components() {
+            buf.push(component.as_str())?;
+            if let Component::Normal(_) = component {
+                match self.stat(&*buf, &mut info) {
+                    Ok(()) if info.entry_type == EntryType::Directory => (),
+                    Ok(()) => return Err(FsError::NotDir),
+                    Err(FsError::Noent) => self.mkdir(&*buf)?,
+                    Err(err) => return Err(err),
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Remove the tree at `buf` depth first. Each step reads the first entry of the deepest
+    /// directory reached so far, removing it if it is a file and descending into it otherwise;
+    /// a directory is removed once it is empty.
+    fn remove_dir_all_in(&mut self, buf: &mut PathBuf) -> Result<(), FsError> {
+        let mut dir = Dir::default();
+        let mut depth = 0;
+        loop {
+            match self.first_entry(&mut dir, buf)? {
+                Some(info) => {
+                    let name = info.name.as_str().map_err(|_| FsError::Inval)?;
+                    buf.push(name)?;
+                    match info.entry_type {
+                        EntryType::Directory => depth += 1,
+                        EntryType::RegularFile => {
+                            self.remove(&*buf)?;
+                            buf.pop();
+                        }
+                    }
+                }
+                None => {
+                    self.remove(&*buf)?;
+                    if depth == 0 {
+                        return Ok(());
+                    }
+                    buf.pop();
+                    depth -= 1;
+                }
+            }
+        }
+    }
+
+    /// Read the first entry of a directory other than `.` and `..`. The directory is closed
+    /// again so that its entries may be removed.
+    fn first_entry(&mut self, dir: &mut Dir, path: &Path) -> Result<Option<Info>, FsError> {
+        self.dir_open(dir, path)?;
+        let res = loop {
+            match self.dir_read(dir) {
+                Ok(Some(ref info)) if info.name == "." || info.name == ".." => (),
+                res => break res,
+            }
+        };
+        self.close_dir_after(dir, res)
+    }
+
+    /// Close `dir` after reading it with result `res`. If the read failed its error and context
+    /// are kept and the close is only attempted.
+    pub(crate) fn close_dir_after<R>(
+        &mut self,
+        dir: &mut Dir,
+        res: Result<R, FsError>,
+    ) -> Result<R, FsError> {
+        let last_error = self.last_error;
+        let closed = self.dir_close(dir);
+        match res {
+            Ok(value) => closed.map(|()| value),
+            Err(error) => {
+                self.last_error = last_error;
+                Err(error)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use crate::tests::RamStorage;
+    use crate::{File, FileOpenFlags};
+
+    extern crate std;
+
+    fn touch(lfs: &mut LittleFs<RamStorage>, path: &str) {
+        let mut file = File::default();
+        lfs.file_open(
+            &mut file,
+            path,
+            FileOpenFlags::WRONLY | FileOpenFlags::CREAT,
+        )
+        .unwrap();
+        lfs.file_write(&mut file, b"data").unwrap();
+        lfs.file_close(&mut file).unwrap();
+    }
+
+    #[test]
+    fn test_create_dir_all() {
+        let mut lfs = LittleFs::new(RamStorage::default());
+        lfs.format().unwrap();
+        let mut lfs = lfs.mount().unwrap();
+
+        lfs.create_dir_all("/a/b/c").unwrap();
+        lfs.create_dir_all("/a//b/./d/../c/").unwrap();
+        lfs.create_dir_all("/").unwrap();
+        let mut info = Info::default();
+        lfs.stat("/a/b/c", &mut info).unwrap();
+        assert_eq!(info.entry_type, EntryType::Directory);
+
+        touch(&mut lfs, "/a/file");
+        assert_eq!(lfs.create_dir_all("/a/file/d"), Err(FsError::NotDir));
+        let context = lfs.last_error().unwrap();
+        assert_eq!(context.operation(), Operation::CreateDirAll);
+        #[cfg(feature = "error-path")]
+        assert_eq!(context.path(), Some("/a/file"));
+        lfs.unmount().unwrap();
+    }
+
+    #[test]
+    fn test_remove_dir_all() {
+        let mut lfs = LittleFs::new(RamStorage::default());
+        lfs.format().unwrap();
+        let mut lfs = lfs.mount().unwrap();
+
+        lfs.create_dir_all("/data/logs/old").unwrap();
+        lfs.mkdir("/data/empty").unwrap();
+        touch(&mut lfs, "/data/config");
+        touch(&mut lfs, "/data/logs/0.txt");
+        touch(&mut lfs, "/data/logs/1.txt");
+        touch(&mut lfs, "/data/logs/old/0.txt");
+        touch(&mut lfs, "/keep");
+
+        lfs.remove_dir_all("/data").unwrap();
+        let mut info = Info::default();
+        assert_eq!(lfs.stat("/data", &mut info), Err(FsError::Noent));
+        lfs.stat("/keep", &mut info).unwrap();
+
+        assert_eq!(lfs.remove_dir_all("/data"), Err(FsError::Noent));
+        let context = lfs.last_error().unwrap();
+        assert_eq!(context.operation(), Operation::DirOpen);
+        #[cfg(feature = "error-path")]
+        assert_eq!(context.path(), Some("/data"));
+
+        assert_eq!(lfs.remove_dir_all("/keep"), Err(FsError::NotDir));
+
+        // The normalized path is walked, so names below it fit even when the given path is long.
+        lfs.mkdir("/long").unwrap();
+        let name = "f".repeat(100);
+        touch(&mut lfs, &std::format!("/long/{}", name));
+        let path = std::format!("/long{}", "/.".repeat(100));
+        lfs.remove_dir_all(&*path).unwrap();
+        assert_eq!(lfs.stat("/long", &mut info), Err(FsError::Noent));
+        assert_eq!(lfs.remove_dir_all("/long/./sub/.."), Err(FsError::Noent));
+        #[cfg(feature = "error-path")]
+        assert_eq!(lfs.last_error().unwrap().path(), Some("/long"));
+
+        // The root cannot be removed, and is left untouched when asked to.
+        for root in &["/", ".", "", "/keep/..", "/../"] {
+            assert_eq!(lfs.remove_dir_all(root), Err(FsError::Inval));
+            assert_eq!(
+                lfs.last_error().unwrap().operation(),
+                Operation::RemoveDirAll
+            );
+        }
+        lfs.stat("/keep", &mut info).unwrap();
+        lfs.unmount().unwrap();
+    }
+}
@@ -0,0 +1,241 @@
+//! Saving and loading serde values.
+//!
+//! With the `serde` feature a value can be stored in a file in the compact postcard encoding,
+//! preceded by a header holding the version of its schema as a little endian `u32`. Files are
+//! replaced with [`LittleFs::write_atomic`], so that after a power loss a file holds either the old
+//! or the new value. Loading a file saved with another schema version fails with
+//! `Error::Version`, naming the version found so that the caller can load it with the matching
+//! type and migrate it:
+//!
+//! ```ignore
+//! #[derive(Serialize, Deserialize)]
+//! struct Config<'a> {
+//!     baud: u32,
+//!     name: &'a str,
+//! }
+//!
+//! let mut buf = [0u8; 64];
+//! fs.save("/config", 2, &Config { baud: 115200, name: "uart0" }, &mut buf)?;
+//! let config: Config = fs.load("/config", 2, &mut buf)?;
+//! ```
+//!
+//! Both directions go through a buffer from the caller, which holds the header and the encoded
+//! value, and values may borrow from it when loaded.
+//!
+//! [`LittleFs::write_atomic`]: ../struct.LittleFs.html#method.write_atomic
+
+use core::convert::TryInto;
+use core::fmt;
+
+use serde::{Deserialize, Serialize};
+
+use crate::path::Path;
+use crate::{FsError, LittleFs, Storage};
+
+/// Length of the header holding the schema version.
+pub const HEADER_LEN: usize = 4;
+
+/// Failure of saving or loading a value.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Error {
+    /// A filesystem call failed; `LittleFs::last_error` holds its context.
+    Fs(FsError),
+    /// The value could not be encoded, for example because it does not fit in the buffer.
+    Encode(postcard::Error),
+    /// The file does not hold a valid encoding of the value.
+    Decode(postcard::Error),
+    /// The file was saved with another schema version.
+    Version { expected: u32, found: u32 },
+}
+
+impl From<FsError> for Error {
+    fn from(err: FsError) -> Self {
+        Error::Fs(err)
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Error::Fs(ref err) => write!(f, "{}", err),
+            Error::Encode(ref err) => write!(f, "encoding failed: {}", err),
+            Error::Decode(ref err) => write!(f, "decoding failed: {}", err),
+            Error::Version { expected, found } => write!(
+                f,
+                "schema version {} where {} was expected",
+                found, expected
+            ),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match *self {
+            Error::Fs(ref err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+impl<T: Storage, const READ_SIZE: usize, const PROG_SIZE: usize, const LOOKAHEAD_SIZE: usize>
+    LittleFs<T, READ_SIZE, PROG_SIZE, LOOKAHEAD_SIZE>
+{
+    /// Save `value` with schema `version` to a file, replacing it atomically. The file is
+    /// encoded in `buf`, which must hold the header and the encoded value.
+    pub fn save<P: AsRef<Path>, V: Serialize + ?Sized>(
+        &mut self,
+        path: P,
+        version: u32,
+        value: &V,
+        buf: &mut [u8],
+    ) -> Result<(), Error> {
+        if buf.len() < HEADER_LEN {
+            return Err(Error::Encode(postcard::Error::SerializeBufferFull));
+        }
+        let (header, body) = buf.split_at_mut(HEADER_LEN);
+        header.copy_from_slice(&version.to_le_bytes());
+        let len = postcard::to_slice(value, body)
+            .map_err(Error::Encode)?
+            .len();
+        self.write_atomic(path, &buf[..HEADER_LEN + len])?;
+        Ok(())
+    }
+
+    /// Load a value saved with schema `version` from a file, reading it into `buf`. The value
+    /// may borrow from `buf`.
+    pub fn load<'de, P: AsRef<Path>, V: Deserialize<'de>>(
+        &mut self,
+        path: P,
+        version: u32,
+        buf: &'de mut [u8],
+    ) -> Result<V, Error> {
+        let len = self.read_into(path, buf)?;
+        let data: &'de [u8] = &buf[..len];
+        if len < HEADER_LEN {
+            return Err(Error::Decode(postcard::Error::DeserializeUnexpectedEnd));
+        }
+        let (header, body) = data.split_at(HEADER_LEN);
+        let found = u32::from_le_bytes(header.try_into().unwrap());
+        if found != version {
+            return Err(Error::Version {
+                expected: version,
+                found,
+            });
+        }
+        postcard::from_bytes(body).map_err(Error::Decode)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use crate::tests::RamStorage;
+    use crate::{Info, Operation};
+
+    extern crate std;
+    use std::string::ToString;
+
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    struct Config<'a> {
+        baud: u32,
+        name: &'a str,
+        enabled: bool,
+    }
+
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    struct ConfigV1 {
+        baud: u16,
+    }
+
+    #[test]
+    fn test_save_load() {
+        let mut lfs = LittleFs::new(RamStorage::default());
+        lfs.format().unwrap();
+        let mut lfs = lfs.mount().unwrap();
+        let mut buf = [0u8; 64];
+
+        let config = Config {
+            baud: 115_200,
+            name: "uart0",
+            enabled: true,
+        };
+        lfs.save("/config", 2, &config, &mut buf).unwrap();
+        let mut info = Info::default();
+        lfs.stat("/config", &mut info).unwrap();
+        assert_eq!(info.size, HEADER_LEN + 3 + 1 + 5 + 1);
+
+        let storage = lfs.unmount().unwrap();
+        let mut lfs = LittleFs::new(storage).mount().unwrap();
+        let mut buf = [0u8; 64];
+        assert_eq!(lfs.load::<_, Config>("/config", 2, &mut buf), Ok(config));
+
+        // An older file is reported with its version so that it can be migrated.
+        lfs.save("/config", 1, &ConfigV1 { baud: 9600 }, &mut buf)
+            .unwrap();
+        let err = lfs.load::<_, Config>("/config", 2, &mut buf).unwrap_err();
+        assert_eq!(
+            err,
+            Error::Version {
+                expected: 2,
+                found: 1
+            }
+        );
+        assert_eq!(err.to_string(), "schema version 1 where 2 was expected");
+        assert_eq!(
+            lfs.load("/config", 1, &mut buf),
+            Ok(ConfigV1 { baud: 9600 })
+        );
+        lfs.unmount().unwrap();
+    }
+
+    #[test]
+    fn test_save_load_errors() {
+        let mut lfs = LittleFs::new(RamStorage::default());
+        lfs.format().unwrap();
+        let mut lfs = lfs.mount().unwrap();
+        let mut buf = [0u8; 64];
+
+        assert_eq!(
+            lfs.load::<_, ConfigV1>("/missing", 1, &mut buf),
+            Err(Error::Fs(FsError::Noent))
+        );
+        assert_eq!(lfs.last_error().unwrap().operation(), Operation::FileOpen);
+
+        let config = Config {
+            baud: 115_200,
+            name: "uart0",
+            enabled: true,
+        };
+        for len in &[0, HEADER_LEN, HEADER_LEN + 4] {
+            assert_eq!(
+                lfs.save("/config", 1, &config, &mut buf[..*len]),
+                Err(Error::Encode(postcard::Error::SerializeBufferFull))
+            );
+        }
+        assert_eq!(
+            lfs.stat("/config", &mut Info::default()),
+            Err(FsError::Noent)
+        );
+
+        lfs.save("/config", 1, &config, &mut buf).unwrap();
+        assert_eq!(
+            lfs.load::<_, Config>("/config", 1, &mut buf[..8]),
+            Err(Error::Fs(FsError::FBig))
+        );
+
+        lfs.write("/config", &[1, 0]).unwrap();
+        assert!(matches!(
+            lfs.load::<_, ConfigV1>("/config", 1, &mut buf),
+            Err(Error::Decode(_))
+        ));
+        lfs.write("/config", &[1, 0, 0, 0, 0x80]).unwrap();
+        assert!(matches!(
+            lfs.load::<_, ConfigV1>("/config", 1, &mut buf),
+            Err(Error::Decode(_))
+        ));
+        lfs.unmount().unwrap();
+    }
+}
@@ -0,0 +1,390 @@
+//! Per-block wear tracking for storage devices.
+//!
+//! [`WearTracker`] wraps any [`Storage`] implementation and counts the reads, programs and erases
+//! issued against every block. The counters live in a separate [`WearCounters`] instance so that
+//! they can still be inspected while the wrapped storage is owned by a mounted [`LittleFs`].
+//!
+//! On targets with atomic compare and swap the counters are atomic, so a [`WearCounters`] may be
+//! kept in a `static` and read from another context, such as a diagnostics task:
+//!
+//! ```ignore
+//! static WEAR: WearCounters = WearCounters::new();
+//!
+//! let fs = LittleFs::new(WearTracker::new(flash, &WEAR));
+//! ```
+//!
+//! [`LittleFs`]: ../struct.LittleFs.html
+
+#[cfg(not(target_has_atomic = "32"))]
+use core::cell::Cell;
+use core::ops::Range;
+#[cfg(target_has_atomic = "32")]
+use core::sync::atomic::{AtomicU32, Ordering};
+
+use crate::{FsError, Storage, BLOCK_COUNT, BLOCK_SIZE};
+
+/// Number of buckets in the erase count histogram of a [`WearReport`].
+pub const HISTOGRAM_BUCKETS: usize = 8;
+
+/// Saturating counter, atomic where the target supports it.
+#[derive(Debug, Default)]
+struct Counter {
+    #[cfg(target_has_atomic = "32")]
+    value: AtomicU32,
+    #[cfg(not(target_has_atomic = "32"))]
+    value: Cell<u32>,
+}
+
+impl Counter {
+    #[cfg(target_has_atomic = "32")]
+    const fn new() -> Self {
+        Counter {
+            value: AtomicU32::new(0),
+        }
+    }
+
+    #[cfg(not(target_has_atomic = "32"))]
+    const fn new() -> Self {
+        Counter {
+            value: Cell::new(0),
+        }
+    }
+
+    #[cfg(target_has_atomic = "32")]
+    fn get(&self) -> u32 {
+        self.value.load(Ordering::Relaxed)
+    }
+
+    #[cfg(not(target_has_atomic = "32"))]
+    fn get(&self) -> u32 {
+        self.value.get()
+    }
+
+    #[cfg(target_has_atomic = "32")]
+    fn bump(&self) {
+        let _ = self
+            .value
+            .fetch_update(Ordering::Relaxed, Ordering::Relaxed, |count| {
+                count.checked_add(1)
+            });
+    }
+
+    #[cfg(not(target_has_atomic = "32"))]
+    fn bump(&self) {
+        self.value.set(self.value.get().saturating_add(1));
+    }
+
+    #[cfg(target_has_atomic = "32")]
+    fn reset(&self) {
+        self.value.store(0, Ordering::Relaxed);
+    }
+
+    #[cfg(not(target_has_atomic = "32"))]
+    fn reset(&self) {
+        self.value.set(0);
+    }
+}
+
+/// Operation counters for a single block of storage.
+#[derive(Debug, Default)]
+pub struct BlockWear {
+    reads: Counter,
+    programs: Counter,
+    erases: Counter,
+}
+
+impl BlockWear {
+    #[allow(clippy::declare_interior_mutable_const)]
+    const NEW: BlockWear = BlockWear {
+        reads: Counter::new(),
+        programs: Counter::new(),
+        erases: Counter::new(),
+    };
+
+    /// Number of read operations issued against the block.
+    pub fn reads(&self) -> u32 {
+        self.reads.get()
+    }
+
+    /// Number of program operations issued against the block.
+    pub fn programs(&self) -> u32 {
+        self.programs.get()
+    }
+
+    /// Number of times the block was erased.
+    pub fn erases(&self) -> u32 {
+        self.erases.get()
+    }
+
+    fn reset(&self) {
+        self.reads.reset();
+        self.programs.reset();
+        self.erases.reset();
+    }
+}
+
+/// Wear counters for every block of the filesystem. The counters are `Sync` on targets with
+/// atomic compare and swap.
+pub struct WearCounters {
+    blocks: [BlockWear; BLOCK_COUNT],
+}
+
+impl Default for WearCounters {
+    fn default() -> Self {
+        WearCounters::new()
+    }
+}
+
+impl WearCounters {
+    /// Create a new set of counters with every block at zero.
+    pub const fn new() -> Self {
+        WearCounters {
+            blocks: [BlockWear::NEW; BLOCK_COUNT],
+        }
+    }
+
+    /// Counters of the given block, if it exists.
+    pub fn block(&self, block: usize) -> Option<&BlockWear> {
+        self.blocks.get(block)
+    }
+
+    /// Iterate over the counters of all blocks in order.
+    pub fn blocks(&self) -> impl Iterator<Item = &BlockWear> {
+        self.blocks.iter()
+    }
+
+    /// Set all counters back to zero.
+    pub fn reset(&self) {
+        for block in self.blocks.iter() {
+            block.reset();
+        }
+    }
+
+    /// Summarize erase counts across all blocks.
+    pub fn report(&self) -> WearReport {
+        self.report_blocks(0..BLOCK_COUNT)
+    }
+
+    /// Summarize erase counts across the given range of blocks. The range is clamped to the
+    /// number of blocks available.
+    pub fn report_blocks(&self, blocks: Range<usize>) -> WearReport {
+        let end = blocks.end.min(BLOCK_COUNT);
+        let start = blocks.start.min(end);
+        WearReport::from_erase_counts(self.blocks[start..end].iter().map(BlockWear::erases))
+    }
+
+    fn block_of(&self, off: usize) -> Option<&BlockWear> {
+        self.blocks.get(off / BLOCK_SIZE)
+    }
+}
+
+/// Summary of the erase counts of a set of blocks.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct WearReport {
+    /// Number of blocks included in the report.
+    pub blocks: usize,
+    /// Sum of the erase counts of all blocks.
+    pub total_erases: u64,
+    /// Lowest erase count of any block.
+    pub min: u32,
+    /// Highest erase count of any block.
+    pub max: u32,
+    /// Average erase count per block.
+    pub mean: f32,
+    /// Range of erase counts covered by each histogram bucket.
+    pub bucket_width: u32,
+    /// Number of blocks per erase count bucket; bucket `i` holds blocks erased between
+    /// `i * bucket_width` and `(i + 1) * bucket_width - 1` times.
+    pub histogram: [u32; HISTOGRAM_BUCKETS],
+}
+
+impl WearReport {
+    fn from_erase_counts<I>(counts: I) -> Self
+    where
+        I: Iterator<Item = u32> + Clone,
+    {
+        let mut blocks = 0;
+        let mut total_erases = 0u64;
+        let mut min = u32::MAX;
+        let mut max = 0;
+        for count in counts.clone() {
+            blocks += 1;
+            total_erases += u64::from(count);
+            min = min.min(count);
+            max = max.max(count);
+        }
+        if blocks == 0 {
+            min = 0;
+        }
+
+        let buckets = HISTOGRAM_BUCKETS as u32;
+        let bucket_width = max / buckets + 1;
+        let mut histogram = [0u32; HISTOGRAM_BUCKETS];
+        for count in counts {
+            let bucket = (count / bucket_width).min(buckets - 1);
+            histogram[bucket as usize] += 1;
+        }
+
+        WearReport {
+            blocks,
+            total_erases,
+            min,
+            max,
+            mean: if blocks == 0 {
+                0.0
+            } else {
+                total_erases as f32 / blocks as f32
+            },
+            bucket_width,
+            histogram,
+        }
+    }
+
+    /// Difference between the most and least erased blocks.
+    pub fn spread(&self) -> u32 {
+        self.max - self.min
+    }
+}
+
+/// Storage wrapper that records the reads, programs and erases issued against every block.
+pub struct WearTracker<'a, S: Storage> {
+    storage: S,
+    counters: &'a WearCounters,
+}
+
+impl<'a, S: Storage> WearTracker<'a, S> {
+    /// Wrap the given storage, recording operations into the given counters.
+    pub fn new(storage: S, counters: &'a WearCounters) -> Self {
+        WearTracker { storage, counters }
+    }
+
+    /// Counters this tracker records into.
+    pub fn counters(&self) -> &'a WearCounters {
+        self.counters
+    }
+
+    /// Unwrap the tracker returning the underlying storage.
+    pub fn into_inner(self) -> S {
+        self.storage
+    }
+}
+
+impl<'a, S: Storage> Storage for WearTracker<'a, S> {
+    fn read(&self, off: usize, buf: &mut [u8]) -> Result<usize, FsError> {
+        if let Some(block) = self.counters.block_of(off) {
+            block.reads.bump();
+        }
+        self.storage.read(off, buf)
+    }
+
+    fn write(&mut self, off: usize, data: &[u8]) -> Result<usize, FsError> {
+        if let Some(block) = self.counters.block_of(off) {
+            block.programs.bump();
+        }
+        self.storage.write(off, data)
+    }
+
+    fn erase(&mut self, off: usize, len: usize) -> Result<usize, FsError> {
+        let end = off.checked_add(len).ok_or(FsError::Inval)?;
+        // Blocks past the last counter have no counters either, so stop there.
+        let mut block_off = off;
+        while block_off < end {
+            match self.counters.block_of(block_off) {
+                Some(block) => block.erases.bump(),
+                None => break,
+            }
+            block_off = match block_off.checked_add(BLOCK_SIZE) {
+                Some(next) => next,
+                None => break,
+            };
+        }
+        self.storage.erase(off, len)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use crate::tests::RamStorage;
+    use crate::{FileOpenFlags, LittleFs};
+
+    extern crate std;
+
+    /// Number of times the config file is rewritten by the wear test.
+    const REWRITES: usize = 3000;
+
+    #[test]
+    fn test_wear_report() {
+        let counters = WearCounters::new();
+        let mut storage = WearTracker::new(RamStorage::default(), &counters);
+        storage.erase(0, BLOCK_SIZE * 2).unwrap();
+        storage.erase(BLOCK_SIZE, BLOCK_SIZE).unwrap();
+        storage.write(BLOCK_SIZE + 16, &[0u8; 16]).unwrap();
+        storage.read(0, &mut [0u8; 16]).unwrap();
+
+        assert_eq!(counters.block(0).unwrap().erases(), 1);
+        assert_eq!(counters.block(0).unwrap().reads(), 1);
+        assert_eq!(counters.block(1).unwrap().erases(), 2);
+        assert_eq!(counters.block(1).unwrap().programs(), 1);
+
+        let report = counters.report();
+        assert_eq!(report.blocks, BLOCK_COUNT);
+        assert_eq!(report.total_erases, 3);
+        assert_eq!(report.min, 0);
+        assert_eq!(report.max, 2);
+        assert_eq!(report.spread(), 2);
+        assert_eq!(report.histogram.iter().sum::<u32>(), BLOCK_COUNT as u32);
+
+        assert_eq!(storage.erase(usize::MAX, 1), Err(FsError::Inval));
+        assert_eq!(counters.report().total_erases, 3);
+
+        let report = counters.report_blocks(0..2);
+        assert_eq!(report.blocks, 2);
+        assert_eq!(report.min, 1);
+        assert_eq!(report.mean, 1.5);
+
+        counters.reset();
+        assert_eq!(counters.report().total_erases, 0);
+    }
+
+    #[test]
+    fn test_static_counters() {
+        static COUNTERS: WearCounters = WearCounters::new();
+        let mut storage = WearTracker::new(RamStorage::default(), &COUNTERS);
+        storage.erase(0, BLOCK_SIZE).unwrap();
+        let erases = std::thread::spawn(|| COUNTERS.block(0).unwrap().erases())
+            .join()
+            .unwrap();
+        assert_eq!(erases, 1);
+    }
+
+    #[test]
+    fn test_config_rewrite_wear() {
+        let counters = WearCounters::new();
+        let storage = WearTracker::new(RamStorage::default(), &counters);
+        let mut lfs = LittleFs::new(storage);
+        lfs.format().unwrap();
+        let mut lfs = lfs.mount().unwrap();
+        for i in 0..REWRITES {
+            let mut file = Default::default();
+            lfs.file_open(
+                &mut file,
+                "/config.bin",
+                FileOpenFlags::WRONLY | FileOpenFlags::CREAT | FileOpenFlags::TRUNC,
+            )
+            .unwrap();
+            let config = [i as u8; 64];
+            lfs.file_write(&mut file, &config).unwrap();
+            lfs.file_close(&mut file).unwrap();
+        }
+        lfs.unmount().unwrap();
+
+        // Directory metadata pairs are rewritten in place by littlefs, so the superblock (blocks
+        // 0 and 1) and root directory (blocks 2 and 3) are excluded from the spread check.
+        let report = counters.report_blocks(4..BLOCK_COUNT);
+        assert!(report.total_erases >= REWRITES as u64);
+        assert!(report.min > 0, "{:?}", report);
+        assert!(report.max <= 2 * report.min, "{:?}", report);
+    }
+}
@@ -0,0 +1,127 @@
+//! Images written with a Rust CRC implementation compared against those of the C library.
+//!
+//! Installing an implementation lasts for the whole process and must happen before any
+//! filesystem is mounted, so this test runs in its own binary rather than alongside the unit
+//! tests of the crate.
+
+#![cfg(all(feature = "crc", target_has_atomic = "ptr"))]
+
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use littlefs::crc::{self, Crc};
+use littlefs::{File, FileOpenFlags, FsError, LittleFs, SoftwareCrc, Storage};
+
+/// Default flash erase value.
+const ERASE_VALUE: u8 = 0xFF;
+
+const STORAGE_SIZE: usize = 131072;
+
+/// RAM backed storage whose image can be compared after unmounting.
+struct RamStorage {
+    buf: Vec<u8>,
+}
+
+impl Default for RamStorage {
+    fn default() -> Self {
+        RamStorage {
+            buf: vec![ERASE_VALUE; STORAGE_SIZE],
+        }
+    }
+}
+
+impl Storage for RamStorage {
+    fn read(&self, off: usize, buf: &mut [u8]) -> Result<usize, FsError> {
+        buf.copy_from_slice(&self.buf[off..off + buf.len()]);
+        Ok(buf.len())
+    }
+
+    fn write(&mut self, off: usize, data: &[u8]) -> Result<usize, FsError> {
+        self.buf[off..off + data.len()].copy_from_slice(data);
+        Ok(data.len())
+    }
+
+    fn erase(&mut self, off: usize, len: usize) -> Result<usize, FsError> {
+        for byte in &mut self.buf[off..off + len] {
+            *byte = ERASE_VALUE;
+        }
+        Ok(len)
+    }
+}
+
+/// Alternate implementation processing one bit at a time, counting its calls like a driver
+/// keeping the state of a peripheral.
+struct BitwiseCrc {
+    calls: AtomicUsize,
+}
+
+static BITWISE: BitwiseCrc = BitwiseCrc {
+    calls: AtomicUsize::new(0),
+};
+
+impl Crc for BitwiseCrc {
+    fn update(&self, crc: u32, data: &[u8]) -> u32 {
+        self.calls.fetch_add(1, Ordering::Relaxed);
+        let mut crc = crc;
+        for &byte in data {
+            crc ^= u32::from(byte);
+            for _ in 0..8 {
+                crc = if crc & 1 != 0 {
+                    (crc >> 1) ^ 0xedb88320
+                } else {
+                    crc >> 1
+                };
+            }
+        }
+        crc
+    }
+}
+
+/// Format a fresh storage and write a small tree to it with the CRC currently in use.
+fn write_image() -> RamStorage {
+    let mut lfs = LittleFs::new(RamStorage::default());
+    lfs.format().unwrap();
+    let mut lfs = lfs.mount().unwrap();
+    lfs.mkdir("/etc").unwrap();
+    let mut file = File::default();
+    lfs.file_open(
+        &mut file,
+        "/etc/config",
+        FileOpenFlags::WRONLY | FileOpenFlags::CREAT,
+    )
+    .unwrap();
+    lfs.file_write(&mut file, b"mode=auto").unwrap();
+    lfs.file_close(&mut file).unwrap();
+    lfs.unmount().unwrap()
+}
+
+/// Mount the image with the CRC currently in use and check the tree written by `write_image`.
+fn check_image(storage: RamStorage) {
+    let mut lfs = LittleFs::new(storage).mount().unwrap();
+    let mut file = File::default();
+    let mut buf = [0u8; 16];
+    lfs.file_open(&mut file, "/etc/config", FileOpenFlags::RDONLY)
+        .unwrap();
+    let sz = lfs.file_read(&mut file, &mut buf).unwrap();
+    assert_eq!(&buf[..sz], b"mode=auto");
+    lfs.file_close(&mut file).unwrap();
+    lfs.unmount().unwrap();
+}
+
+#[test]
+fn test_alternate_crc_image() {
+    // Written by the C library before any implementation is installed.
+    let stock = write_image();
+
+    static SOFTWARE: SoftwareCrc = SoftwareCrc;
+    crc::set_crc(&BITWISE).unwrap();
+    crc::set_crc(&BITWISE).unwrap();
+    assert_eq!(crc::set_crc(&SOFTWARE), Err(FsError::Exist));
+
+    let alternate = write_image();
+    assert_ne!(BITWISE.calls.load(Ordering::Relaxed), 0);
+    assert!(stock.buf == alternate.buf, "images differ between CRCs");
+
+    let calls = BITWISE.calls.load(Ordering::Relaxed);
+    check_image(stock);
+    assert_ne!(BITWISE.calls.load(Ordering::Relaxed), calls);
+}
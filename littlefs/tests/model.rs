@@ -0,0 +1,561 @@
+//! Random sequences of filesystem operations checked against an in-memory reference model.
+//!
+//! Every generated operation is executed against both `LittleFs` and a simple model of the
+//! expected filesystem contents, and the observable results, including the error variants, must
+//! be identical. Operations the model considers outside of the defined behavior of littlefs (for
+//! example renaming a directory into itself, or reading from a write only handle) are skipped.
+
+use std::collections::BTreeMap;
+
+use littlefs::{Dir, EntryType, File, FileOpenFlags, FsError, Info, LittleFs, Storage, Whence};
+use proptest::prelude::*;
+
+/// Default flash erase value.
+const ERASE_VALUE: u8 = 0xFF;
+
+const STORAGE_SIZE: usize = 131072;
+
+/// Paths operations are performed on; kept small so that operations collide often.
+const PATHS: &[&str] = &["/a", "/b", "/d", "/a/x", "/d/x", "/d/y"];
+
+/// Directories whose contents are listed.
+const DIRS: &[&str] = &["/", "/a", "/d"];
+
+/// RAM backed storage, handed back by `unmount` so that it can be remounted.
+struct RamStorage {
+    buf: Vec<u8>,
+}
+
+impl Default for RamStorage {
+    fn default() -> Self {
+        RamStorage {
+            buf: vec![ERASE_VALUE; STORAGE_SIZE],
+        }
+    }
+}
+
+impl Storage for RamStorage {
+    fn read(&self, off: usize, buf: &mut [u8]) -> Result<usize, FsError> {
+        buf.copy_from_slice(&self.buf[off..off + buf.len()]);
+        Ok(buf.len())
+    }
+
+    fn write(&mut self, off: usize, data: &[u8]) -> Result<usize, FsError> {
+        self.buf[off..off + data.len()].copy_from_slice(data);
+        Ok(data.len())
+    }
+
+    fn erase(&mut self, off: usize, len: usize) -> Result<usize, FsError> {
+        for byte in &mut self.buf[off..off + len] {
+            *byte = ERASE_VALUE;
+        }
+        Ok(len)
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+enum SeekFrom {
+    Set,
+    Cur,
+    End,
+}
+
+impl SeekFrom {
+    fn whence(self) -> Whence {
+        match self {
+            SeekFrom::Set => Whence::Set,
+            SeekFrom::Cur => Whence::Cur,
+            SeekFrom::End => Whence::End,
+        }
+    }
+}
+
+/// Operations executed against both the filesystem and the model. Paths are indices into
+/// `PATHS` and directories are indices into `DIRS`.
+#[derive(Debug, Clone)]
+enum Op {
+    Mkdir(usize),
+    Open(usize, FileOpenFlags),
+    Write(Vec<u8>),
+    Read(usize),
+    Seek(i32, SeekFrom),
+    Truncate(usize),
+    Close,
+    Rename(usize, usize),
+    Remove(usize),
+    Stat(usize),
+    List(usize),
+    Remount,
+}
+
+/// Observable result of an operation.
+#[derive(Debug, PartialEq)]
+enum Outcome {
+    Skipped,
+    Unit(Result<(), FsError>),
+    Size(Result<usize, FsError>),
+    Data(Result<Vec<u8>, FsError>),
+    Stat(Result<(EntryType, Option<usize>), FsError>),
+    List(Result<Vec<(String, EntryType)>, FsError>),
+}
+
+#[derive(Debug, Clone)]
+enum Node {
+    File(Vec<u8>),
+    Dir,
+}
+
+/// File handle of the model.
+struct OpenFile {
+    path: String,
+    flags: FileOpenFlags,
+    data: Vec<u8>,
+    pos: usize,
+}
+
+impl OpenFile {
+    fn readable(&self) -> bool {
+        self.flags.contains(FileOpenFlags::RDONLY)
+    }
+
+    fn writable(&self) -> bool {
+        self.flags.contains(FileOpenFlags::WRONLY)
+    }
+}
+
+/// Reference model of the filesystem contents.
+#[derive(Default)]
+struct Model {
+    nodes: BTreeMap<String, Node>,
+    open: Option<OpenFile>,
+}
+
+fn parent(path: &str) -> &str {
+    match path.rfind('/') {
+        Some(0) | None => "/",
+        Some(idx) => &path[..idx],
+    }
+}
+
+fn is_within(path: &str, dir: &str) -> bool {
+    path == dir || (path.starts_with(dir) && path[dir.len()..].starts_with('/'))
+}
+
+impl Model {
+    /// Look up an entry, failing the way littlefs does if its parent cannot be traversed.
+    fn lookup(&self, path: &str) -> Result<Option<&Node>, FsError> {
+        let parent = parent(path);
+        if parent != "/" {
+            match self.nodes.get(parent) {
+                None => return Err(FsError::Noent),
+                Some(Node::File(_)) => return Err(FsError::NotDir),
+                Some(Node::Dir) => {}
+            }
+        }
+        Ok(self.nodes.get(path))
+    }
+
+    fn has_children(&self, dir: &str) -> bool {
+        self.nodes.keys().any(|p| p != dir && is_within(p, dir))
+    }
+
+    /// Whether the entry at the given path, or anything below it, is held open.
+    fn is_busy(&self, path: &str) -> bool {
+        match self.open {
+            Some(ref open) => is_within(&open.path, path),
+            None => false,
+        }
+    }
+
+    fn mkdir(&mut self, path: &str) -> Outcome {
+        let res = match self.lookup(path) {
+            Err(err) => Err(err),
+            Ok(Some(_)) => Err(FsError::Exist),
+            Ok(None) => {
+                self.nodes.insert(path.to_string(), Node::Dir);
+                Ok(())
+            }
+        };
+        Outcome::Unit(res)
+    }
+
+    fn open(&mut self, path: &str, flags: FileOpenFlags) -> Outcome {
+        let data = match self.lookup(path) {
+            Err(err) => return Outcome::Unit(Err(err)),
+            Ok(None) if !flags.contains(FileOpenFlags::CREAT) => {
+                return Outcome::Unit(Err(FsError::Noent));
+            }
+            Ok(None) => {
+                self.nodes.insert(path.to_string(), Node::File(Vec::new()));
+                Vec::new()
+            }
+            Ok(Some(Node::Dir)) => return Outcome::Unit(Err(FsError::IsDir)),
+            Ok(Some(Node::File(_))) if flags.contains(FileOpenFlags::EXCL) => {
+                return Outcome::Unit(Err(FsError::Exist));
+            }
+            Ok(Some(Node::File(data))) => data.clone(),
+        };
+        let data = if flags.contains(FileOpenFlags::TRUNC) {
+            Vec::new()
+        } else {
+            data
+        };
+        self.open = Some(OpenFile {
+            path: path.to_string(),
+            flags,
+            data,
+            pos: 0,
+        });
+        Outcome::Unit(Ok(()))
+    }
+
+    fn write(&mut self, buf: &[u8]) -> Outcome {
+        let file = match self.open {
+            Some(ref mut file) if file.writable() => file,
+            _ => return Outcome::Skipped,
+        };
+        if file.flags.contains(FileOpenFlags::APPEND) {
+            file.pos = file.data.len();
+        }
+        if file.pos > file.data.len() {
+            file.data.resize(file.pos, 0);
+        }
+        let end = file.pos + buf.len();
+        if end > file.data.len() {
+            file.data.resize(end, 0);
+        }
+        file.data[file.pos..end].copy_from_slice(buf);
+        file.pos = end;
+        Outcome::Size(Ok(buf.len()))
+    }
+
+    fn read(&mut self, len: usize) -> Outcome {
+        let file = match self.open {
+            Some(ref mut file) if file.readable() => file,
+            _ => return Outcome::Skipped,
+        };
+        let start = file.pos.min(file.data.len());
+        let end = (file.pos + len).min(file.data.len());
+        file.pos += end - start;
+        Outcome::Data(Ok(file.data[start..end].to_vec()))
+    }
+
+    fn seek(&mut self, off: i32, from: SeekFrom) -> Outcome {
+        let file = match self.open {
+            Some(ref mut file) => file,
+            None => return Outcome::Skipped,
+        };
+        let base = match from {
+            SeekFrom::Set => 0,
+            SeekFrom::Cur => file.pos as i64,
+            SeekFrom::End => file.data.len() as i64,
+        };
+        let pos = base + i64::from(off);
+        if pos < 0 {
+            return Outcome::Size(Err(FsError::Inval));
+        }
+        file.pos = pos as usize;
+        Outcome::Size(Ok(file.pos))
+    }
+
+    fn truncate(&mut self, size: usize) -> Outcome {
+        let file = match self.open {
+            Some(ref mut file) if file.writable() => file,
+            _ => return Outcome::Skipped,
+        };
+        file.data.resize(size, 0);
+        Outcome::Unit(Ok(()))
+    }
+
+    fn close(&mut self) -> Outcome {
+        let file = match self.open.take() {
+            Some(file) => file,
+            None => return Outcome::Skipped,
+        };
+        if file.writable() {
+            self.nodes.insert(file.path, Node::File(file.data));
+        }
+        Outcome::Unit(Ok(()))
+    }
+
+    fn rename(&mut self, old: &str, new: &str) -> Outcome {
+        if old == new || is_within(new, old) || self.is_busy(old) || self.is_busy(new) {
+            return Outcome::Skipped;
+        }
+        let old_is_dir = match self.lookup(old) {
+            Err(err) => return Outcome::Unit(Err(err)),
+            Ok(None) => return Outcome::Unit(Err(FsError::Noent)),
+            Ok(Some(node)) => matches!(node, Node::Dir),
+        };
+        match self.lookup(new) {
+            Err(err) => return Outcome::Unit(Err(err)),
+            Ok(None) => {}
+            Ok(Some(Node::File(_))) if old_is_dir => return Outcome::Unit(Err(FsError::IsDir)),
+            Ok(Some(Node::Dir)) if !old_is_dir => return Outcome::Unit(Err(FsError::IsDir)),
+            Ok(Some(Node::Dir)) if self.has_children(new) => {
+                return Outcome::Unit(Err(FsError::NotEmpty));
+            }
+            Ok(Some(_)) => {}
+        }
+
+        let moved: Vec<String> = self
+            .nodes
+            .keys()
+            .filter(|p| is_within(p, old))
+            .cloned()
+            .collect();
+        for path in moved {
+            let node = self.nodes.remove(&path).unwrap();
+            self.nodes.insert(format!("{}{}", new, &path[old.len()..]), node);
+        }
+        Outcome::Unit(Ok(()))
+    }
+
+    fn remove(&mut self, path: &str) -> Outcome {
+        if self.is_busy(path) {
+            return Outcome::Skipped;
+        }
+        let res = match self.lookup(path) {
+            Err(err) => Err(err),
+            Ok(None) => Err(FsError::Noent),
+            Ok(Some(Node::Dir)) if self.has_children(path) => Err(FsError::NotEmpty),
+            Ok(Some(_)) => {
+                self.nodes.remove(path);
+                Ok(())
+            }
+        };
+        Outcome::Unit(res)
+    }
+
+    fn stat(&self, path: &str) -> Outcome {
+        let res = match self.lookup(path) {
+            Err(err) => Err(err),
+            Ok(None) => Err(FsError::Noent),
+            Ok(Some(Node::File(data))) => Ok((EntryType::RegularFile, Some(data.len()))),
+            Ok(Some(Node::Dir)) => Ok((EntryType::Directory, None)),
+        };
+        Outcome::Stat(res)
+    }
+
+    fn list(&self, dir: &str) -> Outcome {
+        if dir != "/" {
+            match self.lookup(dir) {
+                Err(err) => return Outcome::List(Err(err)),
+                Ok(None) => return Outcome::List(Err(FsError::Noent)),
+                Ok(Some(Node::File(_))) => return Outcome::List(Err(FsError::NotDir)),
+                Ok(Some(Node::Dir)) => {}
+            }
+        }
+        let mut entries: Vec<(String, EntryType)> = self
+            .nodes
+            .iter()
+            .filter(|(path, _)| parent(path) == dir)
+            .map(|(path, node)| {
+                let name = path[path.rfind('/').unwrap() + 1..].to_string();
+                let entry_type = match node {
+                    Node::File(_) => EntryType::RegularFile,
+                    Node::Dir => EntryType::Directory,
+                };
+                (name, entry_type)
+            })
+            .collect();
+        entries.sort_by(|a, b| a.0.cmp(&b.0));
+        Outcome::List(Ok(entries))
+    }
+}
+
+/// Filesystem under test along with the storage it is mounted on.
+struct Harness {
+    /// Mounted filesystem, only missing while it is remounted.
+    lfs: Option<Box<LittleFs<RamStorage>>>,
+    file: Option<Box<File>>,
+}
+
+fn name_of(info: &Info) -> String {
+    info.name.as_str().unwrap().to_string()
+}
+
+impl Harness {
+    fn new() -> Self {
+        let mut lfs = LittleFs::new(RamStorage::default());
+        lfs.format().unwrap();
+        Harness {
+            lfs: Some(Box::new(lfs.mount().unwrap())),
+            file: None,
+        }
+    }
+
+    fn lfs(&mut self) -> &mut LittleFs<RamStorage> {
+        self.lfs.as_mut().unwrap()
+    }
+
+    /// Unmount the filesystem and mount the storage it hands back again, so that only one
+    /// filesystem is mounted on the storage at a time.
+    fn remount(&mut self) {
+        let storage = self.lfs.take().unwrap().unmount().unwrap();
+        self.lfs = Some(Box::new(LittleFs::new(storage).mount().unwrap()));
+    }
+
+    fn open(&mut self, path: &str, flags: FileOpenFlags) -> Outcome {
+        let mut file = Box::new(File::default());
+        let res = self.lfs().file_open(&mut file, path, flags);
+        if res.is_ok() {
+            self.file = Some(file);
+        }
+        Outcome::Unit(res)
+    }
+
+    fn close(&mut self) -> Outcome {
+        match self.file.take() {
+            Some(mut file) => Outcome::Unit(self.lfs().file_close(&mut file)),
+            None => Outcome::Skipped,
+        }
+    }
+
+    fn stat(&mut self, path: &str) -> Outcome {
+        let mut info = Info::default();
+        let res = self.lfs().stat(path, &mut info).map(|()| match info.entry_type {
+            EntryType::RegularFile => (EntryType::RegularFile, Some(info.size)),
+            EntryType::Directory => (EntryType::Directory, None),
+        });
+        Outcome::Stat(res)
+    }
+
+    fn list(&mut self, path: &str) -> Outcome {
+        let mut dir = Box::new(Dir::default());
+        if let Err(err) = self.lfs().dir_open(&mut dir, path) {
+            return Outcome::List(Err(err));
+        }
+        let mut entries = Vec::new();
+        while let Some(info) = self.lfs().dir_read(&mut dir).unwrap() {
+            let name = name_of(&info);
+            if name != "." && name != ".." {
+                entries.push((name, info.entry_type));
+            }
+        }
+        self.lfs().dir_close(&mut dir).unwrap();
+        entries.sort_by(|a, b| a.0.cmp(&b.0));
+        Outcome::List(Ok(entries))
+    }
+}
+
+/// Execute an operation against both the model and the filesystem, returning both outcomes.
+/// The filesystem is only exercised if the model considers the operation well defined.
+fn apply(model: &mut Model, harness: &mut Harness, op: &Op) -> (Outcome, Outcome) {
+    let expected = match *op {
+        Op::Mkdir(p) => model.mkdir(PATHS[p]),
+        Op::Open(p, flags) if model.open.is_none() => model.open(PATHS[p], flags),
+        Op::Open(..) => Outcome::Skipped,
+        Op::Write(ref data) => model.write(data),
+        Op::Read(len) => model.read(len),
+        Op::Seek(off, from) => model.seek(off, from),
+        Op::Truncate(size) => model.truncate(size),
+        Op::Close => model.close(),
+        Op::Rename(old, new) => model.rename(PATHS[old], PATHS[new]),
+        Op::Remove(p) => model.remove(PATHS[p]),
+        Op::Stat(p) => model.stat(PATHS[p]),
+        Op::List(d) => model.list(DIRS[d]),
+        Op::Remount => {
+            model.close();
+            Outcome::Unit(Ok(()))
+        }
+    };
+    if expected == Outcome::Skipped {
+        return (expected, Outcome::Skipped);
+    }
+
+    let lfs = harness.lfs.as_mut().unwrap();
+    let actual = match *op {
+        Op::Mkdir(p) => Outcome::Unit(lfs.mkdir(PATHS[p])),
+        Op::Open(p, flags) => harness.open(PATHS[p], flags),
+        Op::Write(ref data) => {
+            let file = harness.file.as_mut().unwrap();
+            Outcome::Size(lfs.file_write(file, data))
+        }
+        Op::Read(len) => {
+            let file = harness.file.as_mut().unwrap();
+            let mut buf = vec![0u8; len];
+            Outcome::Data(lfs.file_read(file, &mut buf).map(|sz| buf[..sz].to_vec()))
+        }
+        Op::Seek(off, from) => {
+            let file = harness.file.as_mut().unwrap();
+            Outcome::Size(lfs.file_seek(file, off as isize, from.whence()))
+        }
+        Op::Truncate(size) => {
+            let file = harness.file.as_mut().unwrap();
+            Outcome::Unit(lfs.file_truncate(file, size))
+        }
+        Op::Close => harness.close(),
+        Op::Rename(old, new) => Outcome::Unit(lfs.rename(PATHS[old], PATHS[new])),
+        Op::Remove(p) => Outcome::Unit(lfs.remove(PATHS[p])),
+        Op::Stat(p) => harness.stat(PATHS[p]),
+        Op::List(d) => harness.list(DIRS[d]),
+        Op::Remount => {
+            harness.close();
+            harness.remount();
+            Outcome::Unit(Ok(()))
+        }
+    };
+    (expected, actual)
+}
+
+fn open_flags() -> impl Strategy<Value = FileOpenFlags> {
+    prop_oneof![
+        Just(FileOpenFlags::RDONLY),
+        Just(FileOpenFlags::RDWR),
+        Just(FileOpenFlags::RDWR | FileOpenFlags::CREAT),
+        Just(FileOpenFlags::WRONLY | FileOpenFlags::CREAT | FileOpenFlags::TRUNC),
+        Just(FileOpenFlags::WRONLY | FileOpenFlags::CREAT | FileOpenFlags::EXCL),
+        Just(FileOpenFlags::RDWR | FileOpenFlags::CREAT | FileOpenFlags::APPEND),
+    ]
+}
+
+fn seek_from() -> impl Strategy<Value = SeekFrom> {
+    prop_oneof![
+        Just(SeekFrom::Set),
+        Just(SeekFrom::Cur),
+        Just(SeekFrom::End)
+    ]
+}
+
+fn op() -> impl Strategy<Value = Op> {
+    let path = 0..PATHS.len();
+    prop_oneof![
+        2 => path.clone().prop_map(Op::Mkdir),
+        4 => (path.clone(), open_flags()).prop_map(|(p, flags)| Op::Open(p, flags)),
+        4 => prop::collection::vec(any::<u8>(), 1..48).prop_map(Op::Write),
+        2 => (0usize..64).prop_map(Op::Read),
+        1 => (-64i32..64, seek_from()).prop_map(|(off, from)| Op::Seek(off, from)),
+        1 => (0usize..96).prop_map(Op::Truncate),
+        3 => Just(Op::Close),
+        2 => (path.clone(), path.clone()).prop_map(|(old, new)| Op::Rename(old, new)),
+        2 => path.clone().prop_map(Op::Remove),
+        1 => path.prop_map(Op::Stat),
+        1 => (0..DIRS.len()).prop_map(Op::List),
+        1 => Just(Op::Remount),
+    ]
+}
+
+proptest! {
+    #![proptest_config(ProptestConfig::with_cases(64))]
+
+    #[test]
+    fn test_ops_match_model(ops in prop::collection::vec(op(), 1..64)) {
+        let mut model = Model::default();
+        let mut harness = Harness::new();
+
+        for (i, op) in ops.iter().enumerate() {
+            let (expected, actual) = apply(&mut model, &mut harness, op);
+            prop_assert_eq!(expected, actual, "operation {} {:?}", i, op);
+        }
+
+        // Everything must survive a final close and remount.
+        apply(&mut model, &mut harness, &Op::Remount);
+        for path in PATHS {
+            prop_assert_eq!(model.stat(path), harness.stat(path), "stat {}", path);
+        }
+        for dir in DIRS {
+            prop_assert_eq!(model.list(dir), harness.list(dir), "list {}", dir);
+        }
+    }
+}